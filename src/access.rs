@@ -0,0 +1,64 @@
+use wasm_bindgen::prelude::*;
+
+use crate::life_counter_combined::{Cell, Universe};
+
+/// What cell access does with coordinates off the board.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessMode {
+    /// Wraps around the edges, as the board itself does.
+    Wrap = 0,
+    /// Moves onto the nearest edge cell.
+    Clamp = 1,
+    /// Rejects the access. `try_get` and `try_set` return an error, `get`
+    /// reads a dead cell and `set` does nothing.
+    Error = 2,
+}
+
+#[wasm_bindgen]
+impl Universe {
+    pub fn access_mode(&self) -> AccessMode {
+        self.access_mode
+    }
+
+    pub fn set_access_mode(&mut self, mode: AccessMode) {
+        self.access_mode = mode;
+    }
+
+    /// Reads the cell at `(x, y)`, which may be negative or off the board,
+    /// resolved by the access mode.
+    pub fn try_get(&self, x: i32, y: i32) -> Result<Cell, JsValue> {
+        let (x, y) = self
+            .resolve(x as i64, y as i64)
+            .map_err(|err| JsValue::from_str(&err))?;
+        Ok(self.cells[self.index(x, y)])
+    }
+
+    /// Sets the cell at `(x, y)`, which may be negative or off the board,
+    /// resolved by the access mode.
+    pub fn try_set(&mut self, x: i32, y: i32, cell: Cell) -> Result<(), JsValue> {
+        let (x, y) = self
+            .resolve(x as i64, y as i64)
+            .map_err(|err| JsValue::from_str(&err))?;
+        self.set_resolved(x, y, cell);
+        Ok(())
+    }
+}
+
+impl Universe {
+    /// The board cell `(x, y)` refers to under the access mode.
+    pub(crate) fn resolve(&self, x: i64, y: i64) -> Result<(u32, u32), String> {
+        let (width, height) = (self.width as i64, self.height as i64);
+        if (0..width).contains(&x) && (0..height).contains(&y) {
+            return Ok((x as u32, y as u32));
+        }
+        match self.access_mode {
+            AccessMode::Wrap => Ok((x.rem_euclid(width) as u32, y.rem_euclid(height) as u32)),
+            AccessMode::Clamp => Ok((x.clamp(0, width - 1) as u32, y.clamp(0, height - 1) as u32)),
+            AccessMode::Error => Err(format!(
+                "cell ({}, {}) is outside the {}x{} board",
+                x, y, self.width, self.height
+            )),
+        }
+    }
+}
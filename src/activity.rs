@@ -0,0 +1,98 @@
+use wasm_bindgen::prelude::*;
+
+use crate::life_counter_combined::{Cell, Universe};
+
+/// What the canvas colours cells by.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Live cells in their colour from the colour grid.
+    Colour = 0,
+    /// Live cells along the age ramp, from just born to long lived.
+    Age = 1,
+    /// Every cell along the heat ramp by how often it has changed state.
+    /// Cells which have never changed are drawn dead.
+    Activity = 2,
+    /// Live cells by their number of live neighbours.
+    Neighbours = 3,
+}
+
+/// How often each cell has changed state, and when it last did.
+///
+/// A live cell's age is how long ago it last changed, so it needs no
+/// per-tick bookkeeping of its own.
+pub(crate) struct Activity {
+    pub(crate) changes: Vec<u32>,
+    last_change: Vec<u32>,
+}
+
+impl Activity {
+    pub(crate) fn new(len: usize, generation: u32) -> Activity {
+        Activity {
+            changes: vec![0; len],
+            last_change: vec![generation; len],
+        }
+    }
+
+    #[inline]
+    pub(crate) fn cell_changed(&mut self, idx: usize, generation: u32) {
+        self.changes[idx] = self.changes[idx].saturating_add(1);
+        self.last_change[idx] = generation;
+    }
+
+    /// Forgets every change, as though the board had just been set up at
+    /// `generation`.
+    pub(crate) fn reset(&mut self, len: usize, generation: u32) {
+        *self = Activity::new(len, generation);
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// How many generations each live cell has been alive for, row by row,
+    /// with zero for dead cells.
+    pub fn ages(&self) -> Vec<u32> {
+        (0..self.cells.len()).map(|idx| self.age(idx)).collect()
+    }
+
+    /// How many times each cell has changed state since the board was last
+    /// set up, row by row.
+    pub fn change_counts(&self) -> Vec<u32> {
+        self.activity.changes.clone()
+    }
+
+    /// The generation each cell last changed state in, row by row.
+    pub fn last_changes(&self) -> Vec<u32> {
+        self.activity.last_change.clone()
+    }
+
+    /// Clears the ages and change counts, counting from the current
+    /// generation.
+    pub fn reset_activity(&mut self) {
+        self.activity.reset(self.cells.len(), self.tick_count);
+        if self.render_mode != RenderMode::Colour {
+            self.resize_canvas(self.cell_size);
+        }
+    }
+
+    pub fn render_mode(&self) -> RenderMode {
+        self.render_mode
+    }
+
+    /// Switches what cells are coloured by, redrawing the canvas.
+    pub fn set_render_mode(&mut self, mode: RenderMode) {
+        self.render_mode = mode;
+        self.resize_canvas(self.cell_size);
+    }
+}
+
+impl Universe {
+    pub(crate) fn age(&self, idx: usize) -> u32 {
+        if self.cells[idx] == Cell::Alive {
+            self.tick_count
+                .saturating_sub(self.activity.last_change[idx])
+        } else {
+            0
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use wasm_bindgen::prelude::*;
+
+use crate::drawing::bresenham;
+use crate::library::PatternLibrary;
+use crate::life_counter_combined::{Cell, Universe};
+use crate::pattern::Pattern;
+use crate::transform::Transform;
+use crate::utils::SplitMix64;
+
+const SPRAY_SEED: u64 = 0x5EED;
+
+/// The footprint of the brush.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BrushShape {
+    /// A `size` by `size` square.
+    Square = 0,
+    /// A disc `size` cells across.
+    Circle = 1,
+    /// The live cells of the brush pattern, in the brush's orientation.
+    /// `size` is ignored.
+    Pattern = 2,
+}
+
+/// A rotation by a number of quarter turns clockwise, after an optional
+/// left-right flip.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Orientation {
+    turns: u8,
+    flipped: bool,
+}
+
+impl Orientation {
+    /// This orientation followed by `transform`.
+    fn then(self, transform: Transform) -> Orientation {
+        let t = self.turns;
+        // Flips are a rotation after a left-right flip, and a left-right flip
+        // after `t` turns equals one before `-t` turns.
+        let (turns, flip) = match transform {
+            Transform::Rotate90 => (t + 1, false),
+            Transform::Rotate180 => (t + 2, false),
+            Transform::Rotate270 => (t + 3, false),
+            Transform::FlipHorizontal => (4 - t, true),
+            Transform::FlipVertical => (6 - t, true),
+            Transform::Transpose => (7 - t, true),
+        };
+        Orientation {
+            turns: turns % 4,
+            flipped: self.flipped != flip,
+        }
+    }
+
+    fn apply(self, pattern: &Pattern) -> Pattern {
+        let mut oriented = if self.flipped {
+            pattern.transformed(Transform::FlipHorizontal)
+        } else {
+            pattern.clone()
+        };
+        match self.turns {
+            1 => oriented = oriented.transformed(Transform::Rotate90),
+            2 => oriented = oriented.transformed(Transform::Rotate180),
+            3 => oriented = oriented.transformed(Transform::Rotate270),
+            _ => {}
+        }
+        oriented
+    }
+}
+
+/// The brush used by `paint` and `paint_stroke`.
+pub(crate) struct Brush {
+    shape: BrushShape,
+    size: u32,
+    // Chance of each cell under the brush being painted.
+    density: f64,
+    pattern: Option<Pattern>,
+    orientation: Orientation,
+    rng: SplitMix64,
+}
+
+impl Brush {
+    pub(crate) fn new() -> Brush {
+        Brush {
+            shape: BrushShape::Square,
+            size: 1,
+            density: 1.0,
+            pattern: None,
+            orientation: Orientation::default(),
+            rng: SplitMix64::new(SPRAY_SEED),
+        }
+    }
+
+    /// The cells under the brush relative to the pointer, which sits at the
+    /// centre of the footprint.
+    fn offsets(&self) -> Vec<(i32, i32)> {
+        match self.shape {
+            BrushShape::Square | BrushShape::Circle => {
+                let size = self.size as i32;
+                let centre = (size - 1) as f64 / 2.0;
+                let limit = (size as f64 / 2.0).powi(2);
+                let mut offsets = Vec::new();
+                for y in 0..size {
+                    for x in 0..size {
+                        let inside = self.shape == BrushShape::Square
+                            || (x as f64 - centre).powi(2) + (y as f64 - centre).powi(2) < limit;
+                        if inside {
+                            offsets.push((x - (size - 1) / 2, y - (size - 1) / 2));
+                        }
+                    }
+                }
+                offsets
+            }
+            BrushShape::Pattern => match &self.pattern {
+                Some(pattern) => {
+                    let pattern = self.orientation.apply(pattern);
+                    let (cx, cy) = ((pattern.width() / 2) as i32, (pattern.height() / 2) as i32);
+                    (0..pattern.height())
+                        .flat_map(|y| (0..pattern.width()).map(move |x| (x, y)))
+                        .filter(|&(x, y)| pattern.get(x, y) == Cell::Alive)
+                        .map(|(x, y)| (x as i32 - cx, y as i32 - cy))
+                        .collect()
+                }
+                None => Vec::new(),
+            },
+        }
+    }
+
+    fn sprays(&mut self) -> bool {
+        self.density >= 1.0
+            || ((self.rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < self.density
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    pub fn brush_shape(&self) -> BrushShape {
+        self.brush.shape
+    }
+
+    pub fn set_brush_shape(&mut self, shape: BrushShape) {
+        self.brush.shape = shape;
+    }
+
+    pub fn brush_size(&self) -> u32 {
+        self.brush.size
+    }
+
+    /// Sets how many cells across square and circular brushes are, at least
+    /// one.
+    pub fn set_brush_size(&mut self, size: u32) {
+        self.brush.size = size.clamp(1, self.width.max(self.height));
+    }
+
+    pub fn brush_density(&self) -> f64 {
+        self.brush.density
+    }
+
+    /// Sets the chance of each cell under the brush being painted, for
+    /// spraying. A density of one paints solidly.
+    pub fn set_brush_density(&mut self, density: f64) -> Result<(), JsValue> {
+        if !(0.0..=1.0).contains(&density) {
+            return Err(JsValue::from_str(&format!(
+                "brush density {} is not between 0 and 1",
+                density
+            )));
+        }
+        self.brush.density = density;
+        Ok(())
+    }
+
+    /// Makes a pattern read from RLE the brush.
+    pub fn set_brush_pattern(&mut self, rle: &str) -> Result<(), JsValue> {
+        let pattern = Pattern::from_rle(rle).map_err(|err| JsValue::from_str(&err))?;
+        self.use_brush_pattern(pattern);
+        Ok(())
+    }
+
+    /// Makes the named library pattern the brush, for stamping.
+    pub fn set_brush_library_pattern(&mut self, name: &str) -> Result<(), JsValue> {
+        let pattern = PatternLibrary::pattern(name)
+            .ok_or_else(|| JsValue::from_str(&format!("unknown pattern '{}'", name)))?;
+        self.use_brush_pattern(pattern);
+        Ok(())
+    }
+
+    /// Makes the clipboard the brush, for stamping. Returns false if the
+    /// clipboard is empty.
+    pub fn set_brush_from_clipboard(&mut self) -> bool {
+        match self.clipboard.clone() {
+            Some(pattern) => {
+                self.use_brush_pattern(pattern);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Turns or flips the brush pattern, on top of its current orientation.
+    pub fn orient_brush(&mut self, transform: Transform) {
+        self.brush.orientation = self.brush.orientation.then(transform);
+    }
+
+    pub fn reset_brush_orientation(&mut self) {
+        self.brush.orientation = Orientation::default();
+    }
+
+    /// Sets the cells under the brush centred on `(x, y)` to `cell`, as a
+    /// single undoable edit.
+    pub fn paint(&mut self, x: i32, y: i32, cell: Cell) {
+        self.paint_stroke(x, y, x, y, cell);
+    }
+
+    /// Drags the brush along the line from `(x0, y0)` to `(x1, y1)`, as a
+    /// single undoable edit. Like `draw_line`, a stroke longer than the
+    /// board is cut short.
+    pub fn paint_stroke(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, cell: Cell) {
+        let offsets = self.brush.offsets();
+        if offsets.is_empty() {
+            return;
+        }
+
+        let (x0, y0, x1, y1) = self.clip_line(x0, y0, x1, y1);
+        self.begin_edit();
+        bresenham(x0, y0, x1, y1, |x, y| {
+            for &(dx, dy) in &offsets {
+                if self.brush.sprays() {
+                    self.put(x + dx, y + dy, cell);
+                }
+            }
+        });
+        self.end_edit();
+    }
+}
+
+impl Universe {
+    fn use_brush_pattern(&mut self, pattern: Pattern) {
+        self.brush.pattern = Some(pattern);
+        self.brush.shape = BrushShape::Pattern;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alive(universe: &Universe) -> Vec<(u32, u32)> {
+        (0..universe.cells.len() as u32)
+            .filter(|&idx| universe.cells[idx as usize] == Cell::Alive)
+            .map(|idx| (idx % universe.width, idx / universe.width))
+            .collect()
+    }
+
+    #[test]
+    fn far_apart_stroke() {
+        let mut universe = Universe::empty(32, 24, 0);
+        universe.recalculate();
+        universe.paint_stroke(i32::MIN, 5, i32::MAX, 5, Cell::Alive);
+        assert_eq!(
+            alive(&universe),
+            (0..32).map(|x| (x, 5)).collect::<Vec<_>>()
+        );
+
+        universe.set_brush_size(3);
+        universe.paint_stroke(i32::MAX, i32::MAX, i32::MIN, i32::MIN, Cell::Dead);
+        assert!(universe.can_undo());
+    }
+
+    #[test]
+    fn square_and_circle() {
+        let mut universe = Universe::empty(16, 16, 0);
+        universe.recalculate();
+        universe.set_brush_size(3);
+        universe.paint(8, 8, Cell::Alive);
+        assert_eq!(alive(&universe).len(), 9);
+
+        universe.set_brush_shape(BrushShape::Circle);
+        universe.set_brush_size(5);
+        universe.paint(2, 2, Cell::Dead);
+        assert_eq!(alive(&universe).len(), 9);
+        universe.paint(-1, -1, Cell::Alive);
+        assert_eq!(alive(&universe).len(), 9 + 21);
+    }
+}
@@ -0,0 +1,254 @@
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+use crate::life_counter_combined::Universe;
+
+/// The most cells a universe may have, keeping every per-cell buffer well
+/// within a 32-bit address space.
+pub const MAX_CELLS: u64 = 1 << 26;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UniverseError {
+    /// The board has no cells.
+    ZeroSize { width: u32, height: u32 },
+    /// The board has more than `MAX_CELLS` cells.
+    TooLarge { width: u32, height: u32 },
+    /// The canvas has more pixels than a `u32` can index.
+    Overflow {
+        width: u32,
+        height: u32,
+        cell_size: u32,
+    },
+}
+
+impl fmt::Display for UniverseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UniverseError::ZeroSize { width, height } => {
+                write!(f, "a {}x{} board has no cells", width, height)
+            }
+            UniverseError::TooLarge { width, height } => write!(
+                f,
+                "a {}x{} board has more than the {} cells allowed",
+                width, height, MAX_CELLS
+            ),
+            UniverseError::Overflow {
+                width,
+                height,
+                cell_size,
+            } => write!(
+                f,
+                "a {}x{} board at {} pixels per cell needs too large a canvas",
+                width, height, cell_size
+            ),
+        }
+    }
+}
+
+impl std::error::Error for UniverseError {}
+
+impl UniverseError {
+    /// Which of the variants this is, without its dimensions.
+    pub fn kind(&self) -> UniverseErrorKind {
+        match self {
+            UniverseError::ZeroSize { .. } => UniverseErrorKind::ZeroSize,
+            UniverseError::TooLarge { .. } => UniverseErrorKind::TooLarge,
+            UniverseError::Overflow { .. } => UniverseErrorKind::Overflow,
+        }
+    }
+}
+
+/// Which way a universe's dimensions are invalid, as seen from JS.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UniverseErrorKind {
+    ZeroSize = 0,
+    TooLarge = 1,
+    Overflow = 2,
+}
+
+/// What JS catches when a universe's dimensions are invalid: the kind of
+/// problem alongside the message describing it.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DimensionError {
+    kind: UniverseErrorKind,
+    message: String,
+}
+
+#[wasm_bindgen]
+impl DimensionError {
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> UniverseErrorKind {
+        self.kind
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_js_string(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl From<UniverseError> for DimensionError {
+    fn from(err: UniverseError) -> DimensionError {
+        DimensionError {
+            kind: err.kind(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<UniverseError> for JsValue {
+    fn from(err: UniverseError) -> JsValue {
+        DimensionError::from(err).into()
+    }
+}
+
+/// Checks that a `width` by `height` board drawn at `cell_size` pixels per
+/// cell can be allocated and indexed.
+pub(crate) fn validate(width: u32, height: u32, cell_size: u32) -> Result<(), UniverseError> {
+    let cells = width as u64 * height as u64;
+    if cells == 0 {
+        return Err(UniverseError::ZeroSize { width, height });
+    }
+    if cells > MAX_CELLS {
+        return Err(UniverseError::TooLarge { width, height });
+    }
+    let pixels = cells.checked_mul(cell_size as u64 * cell_size as u64);
+    if pixels.is_none_or(|pixels| pixels > u32::MAX as u64) {
+        return Err(UniverseError::Overflow {
+            width,
+            height,
+            cell_size,
+        });
+    }
+    Ok(())
+}
+
+/// Sets up a universe, checking its dimensions before anything is
+/// allocated.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct UniverseBuilder {
+    width: u32,
+    height: u32,
+    cell_size: u32,
+    random: bool,
+}
+
+#[wasm_bindgen]
+impl UniverseBuilder {
+    /// A builder for a `width` by `height` board at one pixel per cell,
+    /// filled randomly.
+    pub fn new(width: u32, height: u32) -> UniverseBuilder {
+        UniverseBuilder {
+            width,
+            height,
+            cell_size: 1,
+            random: true,
+        }
+    }
+
+    /// Sets the pixels per cell on each side of the canvas. Zero leaves the
+    /// universe without a canvas.
+    pub fn cell_size(mut self, cell_size: u32) -> UniverseBuilder {
+        self.cell_size = cell_size;
+        self
+    }
+
+    /// Whether to fill the board randomly, rather than leave it empty.
+    pub fn random(mut self, random: bool) -> UniverseBuilder {
+        self.random = random;
+        self
+    }
+
+    /// Builds the universe, throwing a `DimensionError` saying what is wrong
+    /// with the dimensions if they are invalid.
+    pub fn build(&self) -> Result<Universe, JsValue> {
+        Ok(self.try_build()?)
+    }
+}
+
+impl UniverseBuilder {
+    pub fn try_build(&self) -> Result<Universe, UniverseError> {
+        validate(self.width, self.height, self.cell_size)?;
+        let mut universe = Universe::empty(self.width, self.height, self.cell_size);
+        if self.random {
+            universe.init();
+        }
+        Ok(universe)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::life_counter_combined::Cell;
+
+    #[test]
+    fn invalid_dimensions() {
+        let build = |width, height, cell_size| {
+            UniverseBuilder::new(width, height)
+                .cell_size(cell_size)
+                .try_build()
+                .map(|_| ())
+        };
+        assert_eq!(
+            build(0, 10, 1),
+            Err(UniverseError::ZeroSize {
+                width: 0,
+                height: 10
+            })
+        );
+        assert_eq!(
+            build(1 << 14, 1 << 13, 0),
+            Err(UniverseError::TooLarge {
+                width: 1 << 14,
+                height: 1 << 13
+            })
+        );
+        assert_eq!(
+            build(512, 512, 200),
+            Err(UniverseError::Overflow {
+                width: 512,
+                height: 512,
+                cell_size: 200
+            })
+        );
+        assert_eq!(validate(1 << 13, 1 << 13, 0), Ok(()));
+    }
+
+    #[test]
+    fn dimension_errors() {
+        let err = validate(0, 10, 1).unwrap_err();
+        let thrown = DimensionError::from(err.clone());
+        assert_eq!(thrown.kind(), UniverseErrorKind::ZeroSize);
+        assert_eq!(thrown.message(), err.to_string());
+
+        let kind =
+            |width, height, cell_size| validate(width, height, cell_size).unwrap_err().kind();
+        assert_eq!(kind(1 << 14, 1 << 13, 0), UniverseErrorKind::TooLarge);
+        assert_eq!(kind(512, 512, 200), UniverseErrorKind::Overflow);
+    }
+
+    #[test]
+    fn empty_and_random() {
+        let universe = UniverseBuilder::new(64, 32)
+            .cell_size(2)
+            .random(false)
+            .try_build()
+            .unwrap();
+        assert_eq!(universe.canvas.len(), 128 * 64);
+        assert!(universe.cells.iter().all(|&cell| cell == Cell::Dead));
+
+        let universe = UniverseBuilder::new(64, 32).try_build().unwrap();
+        assert!(universe.cells.contains(&Cell::Alive));
+        assert!(universe.cells.contains(&Cell::Dead));
+    }
+}
@@ -0,0 +1,537 @@
+use std::collections::{HashMap, HashSet};
+
+use wasm_bindgen::prelude::*;
+
+use crate::library::name_for_code;
+use crate::life_counter_combined::{Cell, Universe};
+use crate::motion::{normalise, Motion, Normalised};
+use crate::rule::Rule;
+
+// Empty border around an object when it is run on its own.
+const ISOLATION_PADDING: u32 = 16;
+
+const WECHSLER_DIGITS: &[u8; 32] = b"0123456789abcdefghijklmnopqrstuv";
+
+/// Code given to objects which do not repeat within the period limit.
+pub const PATHOLOGICAL: &str = "PATHOLOGICAL";
+
+/// One separated object found on the board.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CensusObject {
+    pub code: String,
+    /// The object's name in the pattern library, under B3/S23.
+    pub name: Option<&'static str>,
+    pub motion: Option<Motion>,
+    /// Top-left corner of the object's bounding box on the board.
+    pub x: u32,
+    pub y: u32,
+    pub cells: Vec<(u32, u32)>,
+}
+
+/// Object counts by apgcode, most common first.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Census {
+    counts: Vec<(String, u32)>,
+    objects: Vec<CensusObject>,
+}
+
+#[wasm_bindgen]
+impl Census {
+    /// The number of distinct object types.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// The apgcode of object type `i`, or `None` past the last type.
+    pub fn code(&self, i: usize) -> Option<String> {
+        self.counts.get(i).map(|(code, _)| code.clone())
+    }
+
+    /// How many objects of type `i` there are, or `None` past the last type.
+    pub fn count(&self, i: usize) -> Option<u32> {
+        self.counts.get(i).map(|&(_, count)| count)
+    }
+
+    /// The common name of object type `i`, when it is in the pattern
+    /// library.
+    pub fn name(&self, i: usize) -> Option<String> {
+        let (code, _) = self.counts.get(i)?;
+        self.name_for(code).map(str::to_string)
+    }
+
+    /// The total number of objects.
+    pub fn total(&self) -> u32 {
+        self.objects.len() as u32
+    }
+
+    /// One `code count` line per object type, followed by the object's
+    /// name in parentheses when it has one.
+    pub fn to_text(&self) -> String {
+        self.counts
+            .iter()
+            .map(|(code, count)| match self.name_for(code) {
+                Some(name) => format!("{} {} ({})\n", code, count, name),
+                None => format!("{} {}\n", code, count),
+            })
+            .collect()
+    }
+}
+
+impl Census {
+    fn name_for(&self, code: &str) -> Option<&'static str> {
+        self.objects
+            .iter()
+            .find(|object| object.code == code)
+            .and_then(|object| object.name)
+    }
+
+    pub fn counts(&self) -> &[(String, u32)] {
+        &self.counts
+    }
+
+    pub fn objects(&self) -> &[CensusObject] {
+        &self.objects
+    }
+}
+
+/// A group of live cells, in board coordinates, and how it behaves when run
+/// on its own.
+struct Cluster {
+    cells: Vec<(u32, u32)>,
+    isolated: Isolated,
+}
+
+impl Cluster {
+    fn new(universe: &Universe, cells: Vec<(u32, u32)>, max_period: u32) -> Cluster {
+        let isolated = Isolated::new(universe, &cells, max_period);
+        Cluster { cells, isolated }
+    }
+}
+
+/// An object copied into its own padded torus.
+struct Isolated {
+    universe: Universe,
+    // Board position of the isolated universe's (0, 0).
+    origin: (u32, u32),
+    // Board position of the object's bounding box.
+    corner: (u32, u32),
+    motion: Option<(Motion, Vec<Normalised>)>,
+}
+
+impl Isolated {
+    fn new(board: &Universe, cells: &[(u32, u32)], max_period: u32) -> Isolated {
+        let mut mask = vec![Cell::Dead; board.cells.len()];
+        for &(x, y) in cells {
+            mask[board.index(x, y)] = Cell::Alive;
+        }
+        let bounds = normalise(&mask, board.width, board.height).unwrap();
+
+        let width = bounds.width + 2 * ISOLATION_PADDING;
+        let height = bounds.height + 2 * ISOLATION_PADDING;
+        let mut universe = Universe::empty(width, height, 0);
+        universe.rule = board.rule;
+        for &(x, y) in &bounds.cells {
+            let idx = universe.index(x + ISOLATION_PADDING, y + ISOLATION_PADDING);
+            universe.cells[idx] = Cell::Alive;
+        }
+        universe.recalculate();
+
+        let origin = (
+            (bounds.x + board.width - ISOLATION_PADDING % board.width) % board.width,
+            (bounds.y + board.height - ISOLATION_PADDING % board.height) % board.height,
+        );
+        let motion = universe.find_motion(max_period);
+
+        Isolated {
+            universe,
+            origin,
+            corner: (bounds.x, bounds.y),
+            motion,
+        }
+    }
+
+    /// Every board cell the object occupies over one period, or over the
+    /// whole search if it never repeated. An object which only settles
+    /// with help, like the queen bee between its blocks, then reaches
+    /// whatever it interacts with.
+    fn footprint(&self, board: &Universe, max_period: u32) -> HashSet<(u32, u32)> {
+        let period = self
+            .motion
+            .as_ref()
+            .map_or(max_period, |(motion, _)| motion.period);
+        let mut universe = self.universe.headless_copy();
+        let mut footprint = HashSet::new();
+        for _ in 0..period {
+            for (idx, _) in universe
+                .cells
+                .iter()
+                .enumerate()
+                .filter(|(_, &c)| c == Cell::Alive)
+            {
+                let x = (idx as u32 % universe.width + self.origin.0) % board.width;
+                let y = (idx as u32 / universe.width + self.origin.1) % board.height;
+                footprint.insert((x, y));
+            }
+            universe.tick();
+        }
+        footprint
+    }
+}
+
+/// Groups cells which are within `radius` of each other, wrapping around.
+fn group(cells: &[(u32, u32)], radius: i64, width: u32, height: u32) -> Vec<Vec<(u32, u32)>> {
+    let lookup: HashMap<(u32, u32), usize> =
+        cells.iter().enumerate().map(|(i, &c)| (c, i)).collect();
+    let mut group_of = vec![usize::MAX; cells.len()];
+    let mut groups = Vec::new();
+
+    for start in 0..cells.len() {
+        if group_of[start] != usize::MAX {
+            continue;
+        }
+        let id = groups.len();
+        let mut members = Vec::new();
+        let mut stack = vec![start];
+        group_of[start] = id;
+
+        while let Some(i) = stack.pop() {
+            let (x, y) = cells[i];
+            members.push((x, y));
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    let nx = (x as i64 + dx).rem_euclid(width as i64) as u32;
+                    let ny = (y as i64 + dy).rem_euclid(height as i64) as u32;
+                    if let Some(&j) = lookup.get(&(nx, ny)) {
+                        if group_of[j] == usize::MAX {
+                            group_of[j] = id;
+                            stack.push(j);
+                        }
+                    }
+                }
+            }
+        }
+        groups.push(members);
+    }
+
+    groups
+}
+
+/// Tries to split a cluster into 8-connected parts which evolve exactly as
+/// they do together, like the two blocks of a pseudo still life.
+fn split(universe: &Universe, cluster: Cluster, max_period: u32) -> Vec<Cluster> {
+    let period = match &cluster.isolated.motion {
+        Some((motion, _)) if !motion.is_spaceship() => motion.period,
+        _ => return vec![cluster],
+    };
+    let parts = group(&cluster.cells, 1, universe.width, universe.height);
+    if parts.len() < 2 {
+        return vec![cluster];
+    }
+
+    // Run each part alone in the cluster's isolated frame and check their
+    // union matches the whole for a full period.
+    let frame = &cluster.isolated;
+    let mut whole = frame.universe.headless_copy();
+    let mut separate: Vec<Universe> = parts
+        .iter()
+        .map(|part| {
+            let mut alone = Universe::empty(whole.width, whole.height, 0);
+            alone.rule = whole.rule;
+            for &(x, y) in part {
+                let ix = (x + universe.width - frame.origin.0) % universe.width;
+                let iy = (y + universe.height - frame.origin.1) % universe.height;
+                let idx = alone.index(ix, iy);
+                alone.cells[idx] = Cell::Alive;
+            }
+            alone.recalculate();
+            alone
+        })
+        .collect();
+
+    for _ in 0..=period {
+        let independent = (0..whole.cells.len()).all(|idx| {
+            let alive = separate.iter().any(|part| part.cells[idx] == Cell::Alive);
+            alive == (whole.cells[idx] == Cell::Alive)
+        });
+        if !independent {
+            return vec![cluster];
+        }
+        whole.tick();
+        for part in separate.iter_mut() {
+            part.tick();
+        }
+    }
+
+    parts
+        .into_iter()
+        .map(|part| Cluster::new(universe, part, max_period))
+        .collect()
+}
+
+/// Encodes bounding-box-relative cells in extended Wechsler format: strips
+/// of five rows, one base-32 digit per column, runs of zeros shortened to
+/// `w`, `x` and `y?`, and strips separated by `z`.
+fn wechsler(cells: &[(u32, u32)], width: u32, height: u32) -> String {
+    let strips = height.div_ceil(5);
+    let mut columns = vec![0u8; (width * strips) as usize];
+    for &(x, y) in cells {
+        columns[((y / 5) * width + x) as usize] |= 1 << (y % 5);
+    }
+
+    let mut code = String::new();
+    for strip in 0..strips {
+        if strip > 0 {
+            code.push('z');
+        }
+        let row = &columns[(strip * width) as usize..((strip + 1) * width) as usize];
+        let end = row.iter().rposition(|&v| v != 0).map_or(0, |end| end + 1);
+
+        let mut zeros = 0;
+        for &value in &row[..end] {
+            if value == 0 {
+                zeros += 1;
+                continue;
+            }
+            push_zeros(&mut code, zeros);
+            zeros = 0;
+            code.push(WECHSLER_DIGITS[value as usize] as char);
+        }
+    }
+    code
+}
+
+fn push_zeros(code: &mut String, mut zeros: usize) {
+    while zeros > 0 {
+        match zeros {
+            1 => code.push('0'),
+            2 => code.push('w'),
+            3 => code.push('x'),
+            _ => {
+                let run = zeros.min(39);
+                code.push('y');
+                code.push(b"0123456789abcdefghijklmnopqrstuvwxyz"[run - 4] as char);
+                zeros -= run;
+                continue;
+            }
+        }
+        return;
+    }
+}
+
+/// The shortest, then alphabetically first, Wechsler code over every phase
+/// and all eight orientations.
+fn canonical_wechsler(phases: &[Normalised]) -> String {
+    let mut best: Option<String> = None;
+    for phase in phases {
+        let (w, h) = (phase.width, phase.height);
+        for orientation in 0..8 {
+            let transposed = orientation >= 4;
+            let cells: Vec<(u32, u32)> = phase
+                .cells
+                .iter()
+                .map(|&(x, y)| {
+                    let x = if orientation & 1 == 1 { w - 1 - x } else { x };
+                    let y = if orientation & 2 == 2 { h - 1 - y } else { y };
+                    if transposed {
+                        (y, x)
+                    } else {
+                        (x, y)
+                    }
+                })
+                .collect();
+            let (cw, ch) = if transposed { (h, w) } else { (w, h) };
+            let code = wechsler(&cells, cw, ch);
+            let better = match &best {
+                Some(best) => (code.len(), &code) < (best.len(), best),
+                None => true,
+            };
+            if better {
+                best = Some(code);
+            }
+        }
+    }
+    best.unwrap_or_default()
+}
+
+/// The apgcode of a pattern with the given motion and phases.
+pub(crate) fn apgcode(motion: &Motion, phases: &[Normalised]) -> String {
+    let prefix = if motion.is_spaceship() {
+        format!("xq{}", motion.period)
+    } else if motion.period == 1 {
+        format!("xs{}", phases[0].cells.len())
+    } else {
+        format!("xp{}", motion.period)
+    };
+    format!("{}_{}", prefix, canonical_wechsler(phases))
+}
+
+impl Universe {
+    /// The placed cluster, if any, owning a cell within two of `footprint`.
+    fn touching(
+        &self,
+        footprint: &HashSet<(u32, u32)>,
+        owners: &HashMap<(u32, u32), usize>,
+    ) -> Option<usize> {
+        footprint.iter().find_map(|&(x, y)| {
+            (-2i64..=2).find_map(|dy| {
+                (-2i64..=2).find_map(|dx| {
+                    let nx = (x as i64 + dx).rem_euclid(self.width as i64) as u32;
+                    let ny = (y as i64 + dy).rem_euclid(self.height as i64) as u32;
+                    owners.get(&(nx, ny)).copied()
+                })
+            })
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// Separates the board into objects and counts them by apgcode, the way
+    /// apgsearch reports a settled soup. Objects which do not repeat within
+    /// `max_period` generations are counted as `PATHOLOGICAL`.
+    pub fn census(&self, max_period: u32) -> Census {
+        let alive: Vec<(u32, u32)> = (0..self.cells.len())
+            .filter(|&idx| self.cells[idx] == Cell::Alive)
+            .map(|idx| (idx as u32 % self.width, idx as u32 / self.width))
+            .collect();
+
+        // Cells within two of each other can affect the same cell next
+        // generation, so start from those groups.
+        let clusters: Vec<Cluster> = group(&alive, 2, self.width, self.height)
+            .into_iter()
+            .map(|cells| Cluster::new(self, cells, max_period))
+            .collect();
+
+        // Merge groups which come within reach of each other at some point
+        // in their cycle, until none do. Clusters are placed one at a time,
+        // each checked against those already placed, and a merged cluster
+        // goes back to be placed again.
+        let mut footprints: Vec<HashSet<(u32, u32)>> = clusters
+            .iter()
+            .map(|cluster| cluster.isolated.footprint(self, max_period))
+            .collect();
+        let mut clusters: Vec<Option<Cluster>> = clusters.into_iter().map(Some).collect();
+        let mut owners: HashMap<(u32, u32), usize> = HashMap::new();
+        let mut unplaced: Vec<usize> = (0..clusters.len()).rev().collect();
+        while let Some(i) = unplaced.pop() {
+            match self.touching(&footprints[i], &owners) {
+                Some(j) => {
+                    for cell in &footprints[j] {
+                        owners.remove(cell);
+                    }
+                    let mut cells = clusters[i].take().unwrap().cells;
+                    cells.extend(clusters[j].take().unwrap().cells);
+                    let merged = Cluster::new(self, cells, max_period);
+                    footprints.push(merged.isolated.footprint(self, max_period));
+                    clusters.push(Some(merged));
+                    unplaced.push(clusters.len() - 1);
+                }
+                None => {
+                    for &cell in &footprints[i] {
+                        owners.insert(cell, i);
+                    }
+                }
+            }
+        }
+
+        let clusters: Vec<Cluster> = clusters
+            .into_iter()
+            .flatten()
+            .flat_map(|cluster| split(self, cluster, max_period))
+            .collect();
+
+        let mut objects: Vec<CensusObject> = clusters
+            .into_iter()
+            .map(|cluster| {
+                let (code, motion) = match &cluster.isolated.motion {
+                    Some((motion, phases)) => (apgcode(motion, phases), Some(*motion)),
+                    None => (PATHOLOGICAL.to_string(), None),
+                };
+                let (x, y) = cluster.isolated.corner;
+                let name = if self.rule == Rule::CONWAY {
+                    name_for_code(&code)
+                } else {
+                    None
+                };
+                CensusObject {
+                    code,
+                    name,
+                    motion,
+                    x,
+                    y,
+                    cells: cluster.cells,
+                }
+            })
+            .collect();
+        objects.sort_by_key(|object| (object.y, object.x));
+
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        for object in &objects {
+            *counts.entry(object.code.clone()).or_insert(0) += 1;
+        }
+        let mut counts: Vec<(String, u32)> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        Census { counts, objects }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::Pattern;
+
+    fn stamp(universe: &mut Universe, rle: &str, x: u32, y: u32) {
+        let pattern = Pattern::from_rle(rle).unwrap();
+        for py in 0..pattern.height() {
+            for px in 0..pattern.width() {
+                universe.set(x + px, y + py, pattern.get(px, py));
+            }
+        }
+    }
+
+    #[test]
+    fn known_mix() {
+        let mut universe = Universe::empty(64, 64, 0);
+        universe.recalculate();
+        stamp(&mut universe, "2o$2o!", 4, 4);
+        stamp(&mut universe, "2o$2o!", 40, 50);
+        stamp(&mut universe, "3o!", 30, 10);
+        stamp(&mut universe, "bo$2bo$3o!", 10, 40);
+
+        let census = universe.census(16);
+        assert_eq!(
+            census.counts(),
+            &[
+                ("xs4_33".to_string(), 2),
+                ("xp2_7".to_string(), 1),
+                ("xq4_153".to_string(), 1),
+            ]
+        );
+        assert_eq!(census.total(), 4);
+        assert_eq!(census.code(1).as_deref(), Some("xp2_7"));
+        assert_eq!(census.count(0), Some(2));
+        assert_eq!(census.name(0).as_deref(), Some("block"));
+        assert_eq!(census.name(2).as_deref(), Some("glider"));
+
+        assert_eq!(census.code(3), None);
+        assert_eq!(census.count(3), None);
+        assert_eq!(census.name(3), None);
+    }
+
+    #[test]
+    fn pseudo_still_life_splits() {
+        let mut universe = Universe::empty(32, 32, 0);
+        universe.recalculate();
+        // Two blocks a cell apart are two objects, not one.
+        stamp(&mut universe, "2ob2o$2ob2o!", 8, 8);
+
+        let census = universe.census(16);
+        assert_eq!(census.counts(), &[("xs4_33".to_string(), 2)]);
+    }
+}
@@ -0,0 +1,190 @@
+use wasm_bindgen::prelude::*;
+
+use crate::life_counter_combined::Universe;
+
+const DEFAULT_TILE_SIZE: u32 = 16;
+const CLEAN: [u32; 4] = [u32::MAX, u32::MAX, 0, 0];
+/// Once more than one tile in this many is dirty, redrawing the whole canvas
+/// in one go is cheaper than drawing each part separately.
+const FULL_SHARE: usize = 4;
+
+/// The parts of the canvas redrawn since they were last collected.
+///
+/// The board is split into square tiles of cells, and each tile keeps the
+/// bounding box of the cells redrawn in it, so a small change costs a small
+/// rectangle however large the tiles are.
+pub(crate) struct DirtyTiles {
+    tile_size: u32,
+    tiles_across: u32,
+    // (min_x, min_y, max_x, max_y) in cells, inclusive, or `CLEAN`.
+    bounds: Vec<[u32; 4]>,
+    // Indices of the tiles which are not clean.
+    dirty: Vec<usize>,
+    // The whole canvas was redrawn.
+    full: bool,
+}
+
+impl DirtyTiles {
+    pub(crate) fn new() -> DirtyTiles {
+        DirtyTiles {
+            tile_size: DEFAULT_TILE_SIZE,
+            tiles_across: 0,
+            bounds: Vec::new(),
+            dirty: Vec::new(),
+            full: true,
+        }
+    }
+
+    /// Marks the whole canvas as redrawn, fitting the tiles to a `width` by
+    /// `height` board.
+    pub(crate) fn mark_all(&mut self, width: u32, height: u32) {
+        self.tiles_across = width.div_ceil(self.tile_size);
+        let tiles = self.tiles_across * height.div_ceil(self.tile_size);
+        self.bounds = vec![CLEAN; tiles as usize];
+        self.dirty.clear();
+        self.full = true;
+    }
+
+    #[inline]
+    pub(crate) fn mark(&mut self, x: u32, y: u32) {
+        if self.full {
+            return;
+        }
+        let tile = ((y / self.tile_size) * self.tiles_across + x / self.tile_size) as usize;
+        let bounds = &mut self.bounds[tile];
+        if *bounds == CLEAN {
+            self.dirty.push(tile);
+        }
+        *bounds = [
+            bounds[0].min(x),
+            bounds[1].min(y),
+            bounds[2].max(x),
+            bounds[3].max(y),
+        ];
+    }
+
+    /// The bounding boxes of the runs of dirty tiles next to each other
+    /// within a row of tiles, or `None` if so many are dirty that the whole
+    /// canvas should be redrawn.
+    fn spans(&mut self) -> Option<Vec<[u32; 4]>> {
+        if self.full || self.dirty.len() * FULL_SHARE > self.bounds.len() {
+            return None;
+        }
+        self.dirty.sort_unstable();
+        let mut spans: Vec<[u32; 4]> = Vec::new();
+        // The tile just after the last span's end.
+        let mut next = None;
+        for &tile in &self.dirty {
+            let bounds = self.bounds[tile];
+            let joins = next == Some(tile) && tile % self.tiles_across as usize != 0;
+            match spans.last_mut() {
+                Some(span) if joins => {
+                    *span = [
+                        span[0].min(bounds[0]),
+                        span[1].min(bounds[1]),
+                        span[2].max(bounds[2]),
+                        span[3].max(bounds[3]),
+                    ]
+                }
+                _ => spans.push(bounds),
+            }
+            next = Some(tile + 1);
+        }
+        Some(spans)
+    }
+
+    fn clear(&mut self) {
+        for &tile in &self.dirty {
+            self.bounds[tile] = CLEAN;
+        }
+        self.dirty.clear();
+        self.full = false;
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// The canvas rectangles redrawn since the last call, as a flat list of
+    /// pixel `x, y, width, height` quadruples, ready for
+    /// `putImageData(data, 0, 0, x, y, width, height)`. Redrawn tiles next to
+    /// each other in a row share a rectangle. The whole canvas is reported
+    /// after anything which redraws all of it, such as `resize`, or once
+    /// enough of it has been redrawn that one rectangle is cheaper.
+    pub fn take_dirty_rects(&mut self) -> Vec<u32> {
+        let cell_size = self.cell_size;
+        let rects = if cell_size == 0 {
+            Vec::new()
+        } else {
+            match self.dirty.spans() {
+                Some(spans) => spans
+                    .iter()
+                    .flat_map(|&[min_x, min_y, max_x, max_y]| {
+                        [
+                            min_x * cell_size,
+                            min_y * cell_size,
+                            (max_x - min_x + 1) * cell_size,
+                            (max_y - min_y + 1) * cell_size,
+                        ]
+                    })
+                    .collect(),
+                None => vec![0, 0, self.canvas_width, self.height * cell_size],
+            }
+        };
+        self.dirty.clear();
+        rects
+    }
+
+    /// Sets the side of the square tiles, in cells, that redrawn cells are
+    /// gathered into. Smaller tiles report less unchanged canvas but more
+    /// rectangles.
+    pub fn set_dirty_tile_size(&mut self, cells: u32) {
+        self.dirty.tile_size = cells.max(1);
+        self.dirty.mark_all(self.width, self.height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::life_counter_combined::{Cell, Universe};
+
+    fn blank(width: u32, height: u32) -> Universe {
+        let mut universe = Universe::empty(width, height, 2);
+        universe.recalculate();
+        universe.take_dirty_rects();
+        universe
+    }
+
+    #[test]
+    fn neighbouring_tiles_share_a_rect() {
+        let mut universe = blank(64, 64);
+        universe.set(14, 3, Cell::Alive);
+        universe.set(17, 5, Cell::Alive);
+        universe.set(56, 3, Cell::Alive);
+        assert_eq!(universe.take_dirty_rects(), vec![28, 6, 8, 6, 112, 6, 2, 2]);
+        assert_eq!(universe.take_dirty_rects(), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn rows_of_tiles_stay_apart() {
+        // The last tile of one row is just before the first of the next.
+        let mut universe = blank(64, 64);
+        universe.set(63, 0, Cell::Alive);
+        universe.set(0, 16, Cell::Alive);
+        assert_eq!(universe.take_dirty_rects(), vec![126, 0, 2, 2, 0, 32, 2, 2]);
+    }
+
+    #[test]
+    fn many_tiles_redraw_everything() {
+        let mut universe = blank(64, 64);
+        for tile in 0..4 {
+            universe.set(tile * 16, tile * 16, Cell::Alive);
+        }
+        assert_eq!(universe.take_dirty_rects().len(), 4 * 4);
+
+        // Five of the sixteen tiles is more than a quarter.
+        for tile in 0..5 {
+            universe.set(tile * 16 % 64 + 1, tile / 4 * 16, Cell::Alive);
+        }
+        assert_eq!(universe.take_dirty_rects(), vec![0, 0, 128, 128]);
+    }
+}
@@ -0,0 +1,261 @@
+use wasm_bindgen::prelude::*;
+
+use crate::life_counter_combined::{Cell, Universe};
+
+/// Batch edits, each made in a single call and recorded as a single undoable
+/// edit. Shapes may run off any edge of the board and wrap around.
+#[wasm_bindgen]
+impl Universe {
+    /// Sets the cell at each `(xs[i], ys[i])` to `cell`. Coordinates off the
+    /// board are resolved by the access mode, and if it rejects any nothing
+    /// is set.
+    pub fn set_cells(&mut self, xs: &[u32], ys: &[u32], cell: Cell) -> Result<(), JsValue> {
+        if xs.len() != ys.len() {
+            return Err(JsValue::from_str(&format!(
+                "got {} x coordinates but {} y coordinates",
+                xs.len(),
+                ys.len()
+            )));
+        }
+        let positions = xs
+            .iter()
+            .zip(ys)
+            .map(|(&x, &y)| self.resolve(x as i64, y as i64))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| JsValue::from_str(&err))?;
+
+        self.begin_edit();
+        for (x, y) in positions {
+            self.set_resolved(x, y, cell);
+        }
+        self.end_edit();
+        Ok(())
+    }
+
+    /// Sets the cells on the line from `(x0, y0)` to `(x1, y1)`, both ends
+    /// included. A line longer than the board is cut short where it would
+    /// start wrapping back over itself.
+    pub fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, cell: Cell) {
+        self.begin_edit();
+        self.line(x0, y0, x1, y1, cell);
+        self.end_edit();
+    }
+
+    /// Sets every cell of the rectangle with its top-left corner at `(x, y)`.
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: u32, height: u32, cell: Cell) {
+        let x = x.rem_euclid(self.width as i32);
+        let y = y.rem_euclid(self.height as i32);
+        self.begin_edit();
+        for dy in 0..height.min(self.height) {
+            for dx in 0..width.min(self.width) {
+                self.put(x + dx as i32, y + dy as i32, cell);
+            }
+        }
+        self.end_edit();
+    }
+
+    /// Sets every cell within `radius` of `(cx, cy)`, a single cell for a
+    /// radius of zero.
+    pub fn fill_circle(&mut self, cx: i32, cy: i32, radius: u32, cell: Cell) {
+        self.begin_edit();
+        self.disc(cx, cy, radius, cell);
+        self.end_edit();
+    }
+
+    /// Sets the region of cells sharing the state of `(x, y)` and connected
+    /// to it through their edges.
+    pub fn flood_fill(&mut self, x: u32, y: u32, cell: Cell) {
+        let (x, y) = (x % self.width, y % self.height);
+        let target = self.get(x, y);
+        if target == cell {
+            return;
+        }
+
+        self.begin_edit();
+        self.set(x, y, cell);
+        let mut stack = vec![(x, y)];
+        while let Some((x, y)) = stack.pop() {
+            let neighbours = [
+                ((x + self.width - 1) % self.width, y),
+                ((x + 1) % self.width, y),
+                (x, (y + self.height - 1) % self.height),
+                (x, (y + 1) % self.height),
+            ];
+            for (nx, ny) in neighbours {
+                if self.get(nx, ny) == target {
+                    self.set(nx, ny, cell);
+                    stack.push((nx, ny));
+                }
+            }
+        }
+        self.end_edit();
+    }
+}
+
+impl Universe {
+    /// Sets the cell at `(x, y)`, wrapping coordinates off the board.
+    #[inline]
+    pub(crate) fn put(&mut self, x: i32, y: i32, cell: Cell) {
+        let x = x.rem_euclid(self.width as i32) as u32;
+        let y = y.rem_euclid(self.height as i32) as u32;
+        self.set(x, y, cell);
+    }
+
+    /// Sets the cells on Bresenham's line from `(x0, y0)` to `(x1, y1)`.
+    pub(crate) fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, cell: Cell) {
+        let (x0, y0, x1, y1) = self.clip_line(x0, y0, x1, y1);
+        bresenham(x0, y0, x1, y1, |x, y| self.put(x, y, cell));
+    }
+
+    /// Moves the line from `(x0, y0)` to `(x1, y1)` to start on the board,
+    /// and shortens it along its own direction to span at most the board's
+    /// width and height. Any longer and it would only wrap around, mostly
+    /// over cells it had already crossed.
+    pub(crate) fn clip_line(&self, x0: i32, y0: i32, x1: i32, y1: i32) -> (i32, i32, i32, i32) {
+        let (width, height) = (self.width as i64, self.height as i64);
+        let (mut dx, mut dy) = (x1 as i64 - x0 as i64, y1 as i64 - y0 as i64);
+        if dx.abs() > width || dy.abs() > height {
+            // Shorten by whichever axis overshoots the board more.
+            if dx.abs() * height >= dy.abs() * width {
+                dy = dy * width / dx.abs();
+                dx = dx.signum() * width;
+            } else {
+                dx = dx * height / dy.abs();
+                dy = dy.signum() * height;
+            }
+        }
+
+        let x0 = (x0 as i64).rem_euclid(width);
+        let y0 = (y0 as i64).rem_euclid(height);
+        (x0 as i32, y0 as i32, (x0 + dx) as i32, (y0 + dy) as i32)
+    }
+
+    /// The cells whose centres are less than `radius + 1/2` from `(cx, cy)`,
+    /// so small discs come out round rather than as diamonds.
+    pub(crate) fn disc(&mut self, cx: i32, cy: i32, radius: u32, cell: Cell) {
+        let (width, height) = (self.width as i64, self.height as i64);
+        // Any larger and the disc covers the whole board anyway.
+        let r = radius.min(self.width.max(self.height)) as i64;
+        let cx = (cx as i64).rem_euclid(width);
+        let cy = (cy as i64).rem_euclid(height);
+        let limit = r * r + r;
+        // Each column and row is visited once, at its nearest offset, since
+        // further wraps of it are only further from the centre.
+        for dy in -r.min((height - 1) / 2)..=r.min(height / 2) {
+            for dx in -r.min((width - 1) / 2)..=r.min(width / 2) {
+                if dx * dx + dy * dy <= limit {
+                    self.put((cx + dx) as i32, (cy + dy) as i32, cell);
+                }
+            }
+        }
+    }
+}
+
+/// Calls `f` with each point on Bresenham's line from `(x0, y0)` to
+/// `(x1, y1)`, both ends included.
+pub(crate) fn bresenham(x0: i32, y0: i32, x1: i32, y1: i32, mut f: impl FnMut(i32, i32)) {
+    let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+    let (step_x, step_y) = ((x1 - x0).signum(), (y1 - y0).signum());
+    let (mut x, mut y) = (x0, y0);
+    let mut error = dx + dy;
+    loop {
+        f(x, y);
+        if x == x1 && y == y1 {
+            break;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blank(width: u32, height: u32) -> Universe {
+        let mut universe = Universe::empty(width, height, 0);
+        universe.recalculate();
+        universe
+    }
+
+    fn alive(universe: &Universe) -> Vec<(u32, u32)> {
+        (0..universe.cells.len() as u32)
+            .filter(|&idx| universe.cells[idx as usize] == Cell::Alive)
+            .map(|idx| (idx % universe.width, idx / universe.width))
+            .collect()
+    }
+
+    #[test]
+    fn short_lines() {
+        let mut universe = blank(16, 16);
+        universe.draw_line(1, 1, 5, 3, Cell::Alive);
+        assert_eq!(alive(&universe), [(1, 1), (2, 2), (3, 2), (4, 3), (5, 3)]);
+
+        // Lines off the edge wrap around.
+        let mut universe = blank(16, 16);
+        universe.draw_line(-2, 0, 1, 0, Cell::Alive);
+        assert_eq!(alive(&universe), [(0, 0), (1, 0), (14, 0), (15, 0)]);
+    }
+
+    #[test]
+    fn far_apart_endpoints() {
+        let mut universe = blank(32, 24);
+        universe.draw_line(i32::MIN, 3, i32::MAX, 3, Cell::Alive);
+        assert_eq!(
+            alive(&universe),
+            (0..32).map(|x| (x, 3)).collect::<Vec<_>>()
+        );
+
+        let mut universe = blank(32, 24);
+        universe.draw_line(i32::MAX, i32::MAX, i32::MIN, i32::MIN, Cell::Alive);
+        assert!(!alive(&universe).is_empty());
+        assert!(universe.can_undo());
+
+        let mut universe = blank(32, 24);
+        universe.draw_line(0, 0, 1 << 30, 1 << 30, Cell::Alive);
+        let mut diagonal: Vec<(u32, u32)> = (0..=24).map(|i| (i % 32, i % 24)).collect();
+        diagonal.sort_by_key(|&(x, y)| (y, x));
+        diagonal.dedup();
+        assert_eq!(alive(&universe), diagonal);
+    }
+
+    #[test]
+    fn far_off_shapes() {
+        let mut universe = blank(32, 24);
+        universe.fill_rect(i32::MAX, i32::MIN, 2, 2, Cell::Alive);
+        assert_eq!(alive(&universe).len(), 4);
+
+        let mut universe = blank(32, 24);
+        universe.fill_circle(i32::MAX, i32::MAX, 1, Cell::Alive);
+        assert_eq!(alive(&universe).len(), 9);
+    }
+
+    #[test]
+    fn huge_discs() {
+        let mut universe = blank(46341, 4);
+        universe.fill_circle(0, 0, 46340, Cell::Alive);
+        assert_eq!(alive(&universe).len(), 46341 * 4);
+
+        let mut universe = blank(32, 24);
+        universe.fill_circle(5, 5, u32::MAX, Cell::Alive);
+        assert_eq!(alive(&universe).len(), 32 * 24);
+    }
+
+    #[test]
+    fn wrapped_disc() {
+        // A disc as wide as a small board still only covers nearby rows.
+        let mut universe = blank(4, 32);
+        universe.fill_circle(0, 10, 2, Cell::Alive);
+        let mut rows: Vec<u32> = alive(&universe).iter().map(|&(_, y)| y).collect();
+        rows.dedup();
+        assert_eq!(rows, vec![8, 9, 10, 11, 12]);
+        assert_eq!(alive(&universe).len(), 3 * 4 + 2 * 3);
+    }
+}
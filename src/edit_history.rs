@@ -0,0 +1,245 @@
+use std::collections::{HashMap, VecDeque};
+
+use wasm_bindgen::prelude::*;
+
+use crate::life_counter_combined::{Cell, Universe};
+
+const DEFAULT_LIMIT: usize = 100;
+
+/// Undo / redo stacks of edit transactions.
+///
+/// Each committed transaction is stored as a compact diff: one `u32` per
+/// changed cell holding `index << 1 | new_state`. Cells are binary, so the
+/// previous state is always the opposite of the new one.
+///
+/// Diffs hold absolute states, which would be wrong for any other board, so
+/// the history is cleared whenever the generation changes.
+pub(crate) struct EditHistory {
+    undo: VecDeque<Vec<u32>>,
+    redo: Vec<Vec<u32>>,
+    // Cell index and state before the change, in edit order.
+    pending: Vec<(u32, Cell)>,
+    depth: u32,
+    limit: usize,
+}
+
+impl EditHistory {
+    pub(crate) fn new() -> EditHistory {
+        EditHistory {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            pending: Vec::new(),
+            depth: 0,
+            limit: DEFAULT_LIMIT,
+        }
+    }
+
+    pub(crate) fn is_recording(&self) -> bool {
+        self.depth > 0
+    }
+
+    pub(crate) fn record(&mut self, idx: usize, old: Cell) {
+        self.pending.push((idx as u32, old));
+    }
+
+    fn begin(&mut self) {
+        self.depth += 1;
+    }
+
+    /// Closes a transaction, committing it once the outermost one ends.
+    fn end(&mut self, cells: &[Cell]) {
+        if self.depth == 0 {
+            return;
+        }
+        self.depth -= 1;
+        if self.depth > 0 {
+            return;
+        }
+
+        // Keep only the first recorded state of each cell and drop cells
+        // which were changed back to what they started as.
+        let mut first: HashMap<u32, Cell> = HashMap::new();
+        for (idx, old) in self.pending.drain(..) {
+            first.entry(idx).or_insert(old);
+        }
+        let mut diff: Vec<u32> = first
+            .into_iter()
+            .filter(|&(idx, old)| cells[idx as usize] != old)
+            .map(|(idx, _)| idx << 1 | cells[idx as usize] as u32)
+            .collect();
+
+        if diff.is_empty() {
+            return;
+        }
+        diff.sort_unstable();
+        self.push_undo(diff);
+        self.redo.clear();
+    }
+
+    fn push_undo(&mut self, diff: Vec<u32>) {
+        self.undo.push_back(diff);
+        while self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.pending.clear();
+        self.depth = 0;
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// Starts an edit transaction. Every cell changed through `set` until the
+    /// matching `end_edit` is undone or redone as a single step. Transactions
+    /// may be nested, only the outermost one is recorded.
+    pub fn begin_edit(&mut self) {
+        self.edit_history.begin();
+    }
+
+    pub fn end_edit(&mut self) {
+        self.edit_history.end(&self.cells);
+    }
+
+    /// Reverts the most recent edit transaction, returning `false` if there
+    /// was nothing to undo. Only edits made since the last tick, seek or
+    /// reverse step can be undone.
+    pub fn undo(&mut self) -> bool {
+        self.close_open_edits();
+        match self.edit_history.undo.pop_back() {
+            Some(diff) => {
+                self.apply_diff(&diff, true);
+                self.edit_history.redo.push(diff);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reapplies the most recently undone transaction, returning `false` if
+    /// there was nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        self.close_open_edits();
+        match self.edit_history.redo.pop() {
+            Some(diff) => {
+                self.apply_diff(&diff, false);
+                self.edit_history.push_undo(diff);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.edit_history.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.edit_history.redo.is_empty()
+    }
+
+    /// Sets how many transactions are kept, dropping the oldest ones.
+    pub fn set_undo_limit(&mut self, limit: usize) {
+        self.edit_history.limit = limit;
+        while self.edit_history.undo.len() > limit {
+            self.edit_history.undo.pop_front();
+        }
+    }
+
+    pub fn clear_edit_history(&mut self) {
+        self.edit_history.clear();
+    }
+}
+
+impl Universe {
+    fn close_open_edits(&mut self) {
+        while self.edit_history.is_recording() {
+            self.end_edit();
+        }
+    }
+
+    fn apply_diff(&mut self, diff: &[u32], revert: bool) {
+        for &entry in diff {
+            let idx = entry >> 1;
+            let alive = (entry & 1 == 1) != revert;
+            let cell = if alive { Cell::Alive } else { Cell::Dead };
+            self.set_cell(idx % self.width, idx / self.width, cell);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blank(width: u32, height: u32) -> Universe {
+        let mut universe = Universe::empty(width, height, 1);
+        universe.recalculate();
+        universe
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut universe = blank(8, 8);
+        universe.begin_edit();
+        universe.set(1, 1, Cell::Alive);
+        universe.set(2, 1, Cell::Alive);
+        universe.end_edit();
+        universe.begin_edit();
+        universe.set(5, 5, Cell::Alive);
+        universe.end_edit();
+        let edited = universe.cells.clone();
+
+        assert!(universe.undo());
+        assert_eq!(universe.get(5, 5), Cell::Dead);
+        assert_eq!(universe.get(2, 1), Cell::Alive);
+        assert!(universe.undo());
+        assert!(universe.cells.iter().all(|&cell| cell == Cell::Dead));
+        assert!(!universe.undo());
+
+        assert!(universe.redo());
+        assert!(universe.redo());
+        assert!(!universe.redo());
+        assert_eq!(universe.cells, edited);
+    }
+
+    #[test]
+    fn ticking_clears_the_history() {
+        let mut universe = blank(16, 16);
+        universe.begin_edit();
+        for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            universe.set(x, y, Cell::Alive);
+        }
+        universe.end_edit();
+        for _ in 0..8 {
+            universe.tick();
+        }
+        let evolved = universe.cells.clone();
+
+        // The glider has moved on, so undoing would kill unrelated cells.
+        assert!(!universe.can_undo());
+        assert!(!universe.undo());
+        assert_eq!(universe.cells, evolved);
+    }
+
+    #[test]
+    fn seeking_clears_the_history() {
+        let mut universe = blank(16, 16);
+        universe.enable_history(16, 4);
+        for x in 4..7 {
+            universe.set(x, 4, Cell::Alive);
+        }
+        universe.tick();
+        universe.begin_edit();
+        universe.set(10, 10, Cell::Alive);
+        universe.end_edit();
+        assert!(universe.can_undo());
+
+        assert!(universe.step_back());
+        assert!(!universe.can_undo());
+        assert!(!universe.undo());
+    }
+}
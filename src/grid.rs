@@ -0,0 +1,123 @@
+use wasm_bindgen::prelude::*;
+
+use crate::life_counter_combined::Universe;
+
+/// Lines drawn between cells on the canvas.
+///
+/// Each cell's block of pixels gives its top rows and left columns to the
+/// lines, so cells are only ever drawn inside them and redrawing a cell
+/// leaves the grid intact.
+pub(crate) struct Grid {
+    enabled: bool,
+    colour: u32,
+    thickness: u32,
+    // Every `major_every` lines uses `major_colour`, or none if zero.
+    major_every: u32,
+    major_colour: u32,
+    // Below this cell size the grid would crowd out the cells.
+    min_cell_size: u32,
+}
+
+impl Grid {
+    pub(crate) fn new() -> Grid {
+        Grid {
+            enabled: false,
+            colour: 0xFF303030,
+            thickness: 1,
+            major_every: 0,
+            major_colour: 0xFF606060,
+            min_cell_size: 4,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// Turns grid lines on or off. They are only drawn while the cell size
+    /// is at least the minimum set by `set_grid_min_cell_size`.
+    pub fn set_grid_enabled(&mut self, enabled: bool) {
+        self.grid.enabled = enabled;
+        self.resize_canvas(self.cell_size);
+    }
+
+    /// Whether grid lines are currently drawn.
+    pub fn grid_visible(&self) -> bool {
+        self.grid_inset() > 0
+    }
+
+    /// Sets the colour of grid lines, as a canvas pixel `0xAABBGGRR`.
+    pub fn set_grid_colour(&mut self, colour: u32) {
+        self.grid.colour = colour;
+        self.resize_canvas(self.cell_size);
+    }
+
+    /// Sets how many pixels wide grid lines are.
+    pub fn set_grid_thickness(&mut self, thickness: u32) {
+        self.grid.thickness = thickness;
+        self.resize_canvas(self.cell_size);
+    }
+
+    /// Draws every `every`th line, starting from the top and left edges, in
+    /// `colour`. An `every` of zero draws no major lines.
+    pub fn set_grid_major(&mut self, every: u32, colour: u32) {
+        self.grid.major_every = every;
+        self.grid.major_colour = colour;
+        self.resize_canvas(self.cell_size);
+    }
+
+    /// Hides the grid while cells are smaller than `cell_size` pixels.
+    pub fn set_grid_min_cell_size(&mut self, cell_size: u32) {
+        self.grid.min_cell_size = cell_size;
+        self.resize_canvas(self.cell_size);
+    }
+}
+
+impl Universe {
+    /// How many pixels at the top and left of each cell belong to the grid.
+    #[inline]
+    pub(crate) fn grid_inset(&self) -> u32 {
+        let grid = &self.grid;
+        let visible = grid.enabled
+            && grid.thickness > 0
+            && self.cell_size >= grid.min_cell_size
+            && grid.thickness < self.cell_size;
+        if visible {
+            grid.thickness
+        } else {
+            0
+        }
+    }
+
+    /// Paints the grid lines onto the canvas.
+    pub(crate) fn draw_grid(&mut self) {
+        let inset = self.grid_inset();
+        if inset == 0 {
+            return;
+        }
+
+        let (every, colour, major_colour) = (
+            self.grid.major_every,
+            self.grid.colour,
+            self.grid.major_colour,
+        );
+        let is_major = |n: u32| every > 0 && n.is_multiple_of(every);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                for j in 0..self.cell_size {
+                    for i in 0..self.cell_size {
+                        if i >= inset && j >= inset {
+                            continue;
+                        }
+                        // Where lines cross, the major one wins.
+                        let major = (i < inset && is_major(x)) || (j < inset && is_major(y));
+                        let canvas_x = x * self.cell_size + i;
+                        let canvas_y = y * self.cell_size + j;
+                        let idx = canvas_y * self.canvas_width + canvas_x;
+
+                        self.canvas[idx as usize] = if major { major_colour } else { colour };
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,8 +1,58 @@
+mod access;
+mod activity;
+mod brush;
+mod builder;
+mod census;
+mod dirty;
+mod drawing;
+mod edit_history;
+mod grid;
+mod library;
 mod life_counter_combined;
 // mod life_counter_pruned;
 // mod life_counter;
 // mod life_vec;
+mod motion;
+mod palette;
+mod pattern;
+mod period;
+mod population;
+mod predecessor;
+mod rewind;
+mod rule;
+mod run;
+mod sat;
+mod search;
+mod selection;
+#[cfg(feature = "serde")]
+mod serialization;
+mod snapshot;
+mod soup;
+mod stats;
+mod transform;
 mod utils;
+mod viewport;
+
+pub use access::AccessMode;
+pub use activity::RenderMode;
+pub use brush::BrushShape;
+pub use builder::{DimensionError, UniverseBuilder, UniverseError, UniverseErrorKind, MAX_CELLS};
+pub use census::{Census, CensusObject};
+pub use library::{PatternKind, PatternLibrary};
+pub use life_counter_combined::{Cell, Universe};
+pub use motion::Motion;
+pub use palette::{GradientPreset, Theme};
+pub use pattern::Pattern;
+pub use period::Periodicity;
+pub use population::BoundingBox;
+pub use rule::Rule;
+pub use run::StopCondition;
+pub use search::PatternSearch;
+pub use selection::PasteMode;
+pub use snapshot::{Snapshot, SnapshotError};
+pub use soup::{RareFind, SoupSearch, Symmetry};
+pub use transform::Transform;
+pub use viewport::Viewport;
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use wasm_bindgen::prelude::*;
+
+use crate::census::apgcode;
+use crate::life_counter_combined::{Cell, Universe};
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+use crate::selection::PasteMode;
+
+// Room for an entry to oscillate in while its apgcode is worked out.
+const PADDING: u32 = 16;
+const MAX_PERIOD: u32 = 60;
+
+/// The kind of object a library pattern is.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatternKind {
+    StillLife = 0,
+    Oscillator = 1,
+    Spaceship = 2,
+    Gun = 3,
+}
+
+struct Entry {
+    name: &'static str,
+    kind: PatternKind,
+    rle: &'static str,
+}
+
+const fn entry(name: &'static str, kind: PatternKind, rle: &'static str) -> Entry {
+    Entry { name, kind, rle }
+}
+
+// Named objects in B3/S23, as RLE bodies.
+const LIBRARY: &[Entry] = &[
+    entry("block", PatternKind::StillLife, "2o$2o!"),
+    entry("beehive", PatternKind::StillLife, "b2o$o2bo$b2o!"),
+    entry("loaf", PatternKind::StillLife, "b2o$o2bo$bobo$2bo!"),
+    entry("boat", PatternKind::StillLife, "2o$obo$bo!"),
+    entry("ship", PatternKind::StillLife, "2o$obo$b2o!"),
+    entry("tub", PatternKind::StillLife, "bo$obo$bo!"),
+    entry("pond", PatternKind::StillLife, "b2o$o2bo$o2bo$b2o!"),
+    entry("long boat", PatternKind::StillLife, "bo$obo$bobo$2b2o!"),
+    entry(
+        "very long boat",
+        PatternKind::StillLife,
+        "bo$obo$bobo$2bobo$3b2o!",
+    ),
+    entry("long ship", PatternKind::StillLife, "2o$obo$bobo$2b2o!"),
+    entry("barge", PatternKind::StillLife, "bo$obo$bobo$2bo!"),
+    entry(
+        "long barge",
+        PatternKind::StillLife,
+        "bo$obo$bobo$2bobo$3bo!",
+    ),
+    entry("mango", PatternKind::StillLife, "b2o$o2bo$bo2bo$2b2o!"),
+    entry("eater 1", PatternKind::StillLife, "2o$obo$2bo$2b2o!"),
+    entry("snake", PatternKind::StillLife, "2obo$ob2o!"),
+    entry("aircraft carrier", PatternKind::StillLife, "2o$o2bo$2b2o!"),
+    entry("shillelagh", PatternKind::StillLife, "2o$o2b2o$b2obo!"),
+    entry(
+        "integral sign",
+        PatternKind::StillLife,
+        "2o$obo$2bo$2bobo$3b2o!",
+    ),
+    entry("canoe", PatternKind::StillLife, "3b2o$4bo$3bo$obo$2o!"),
+    entry("hat", PatternKind::StillLife, "2bo$bobo$bobo$2ob2o!"),
+    entry(
+        "tub with tail",
+        PatternKind::StillLife,
+        "bo$obo$bobo$3bo$3b2o!",
+    ),
+    entry(
+        "ship-tie",
+        PatternKind::StillLife,
+        "2o$obo$b2o$3b2o$3bobo$4b2o!",
+    ),
+    entry(
+        "paperclip",
+        PatternKind::StillLife,
+        "2b2o$bo2bo$bob2o$2obo$o2bo$b2o!",
+    ),
+    entry("blinker", PatternKind::Oscillator, "3o!"),
+    entry("toad", PatternKind::Oscillator, "b3o$3o!"),
+    entry("beacon", PatternKind::Oscillator, "2o$2o$2b2o$2b2o!"),
+    entry("clock", PatternKind::Oscillator, "2bo$obo$bobo$bo!"),
+    entry(
+        "caterer",
+        PatternKind::Oscillator,
+        "2bo$o3b4o$o3bo$o$3bo$b2o!",
+    ),
+    entry(
+        "mold",
+        PatternKind::Oscillator,
+        "3b2o$2bo2bo$o2bobo$4bo$ob2o$bo!",
+    ),
+    entry(
+        "octagon 2",
+        PatternKind::Oscillator,
+        "3b2o$2bo2bo$bo4bo$o6bo$o6bo$bo4bo$2bo2bo$3b2o!",
+    ),
+    entry(
+        "figure eight",
+        PatternKind::Oscillator,
+        "3o$3o$3o$3b3o$3b3o$3b3o!",
+    ),
+    entry(
+        "pulsar",
+        PatternKind::Oscillator,
+        "2b3o3b3o2$o4bobo4bo$o4bobo4bo$o4bobo4bo$2b3o3b3o2$2b3o3b3o$o4bobo4bo$\
+         o4bobo4bo$o4bobo4bo2$2b3o3b3o!",
+    ),
+    entry(
+        "kok's galaxy",
+        PatternKind::Oscillator,
+        "6ob2o$6ob2o$7b2o$2o5b2o$2o5b2o$2o5b2o$2o$2ob6o$2ob6o!",
+    ),
+    entry(
+        "tumbler",
+        PatternKind::Oscillator,
+        "bo5bo$obo3bobo$o2bobo2bo$2bo3bo$2b2ob2o!",
+    ),
+    entry(
+        "pentadecathlon",
+        PatternKind::Oscillator,
+        "2bo4bo$2ob4ob2o$2bo4bo!",
+    ),
+    entry(
+        "queen bee shuttle",
+        PatternKind::Oscillator,
+        "9bo$7bobo$6bobo$2o3bo2bo11b2o$2o4bobo11b2o$7bobo$9bo!",
+    ),
+    entry("glider", PatternKind::Spaceship, "bo$2bo$3o!"),
+    entry("lwss", PatternKind::Spaceship, "bo2bo$o$o3bo$4o!"),
+    entry("mwss", PatternKind::Spaceship, "3bo$bo3bo$o$o4bo$5o!"),
+    entry("hwss", PatternKind::Spaceship, "3b2o$bo4bo$o$o5bo$6o!"),
+    entry(
+        "gosper glider gun",
+        PatternKind::Gun,
+        "24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4bobo$\
+         10bo5bo7bo$11bo3bo$12b2o!",
+    ),
+    entry(
+        "simkin glider gun",
+        PatternKind::Gun,
+        "2o5b2o$2o5b2o2$4b2o$4b2o5$22b2ob2o$21bo5bo$21bo6bo2b2o$21b3o3bo3b2o$26bo4$\
+         20b2o$20bo$21b3o$23bo!",
+    ),
+];
+
+fn find(name: &str) -> Option<&'static Entry> {
+    let name = name.trim();
+    LIBRARY
+        .iter()
+        .find(|entry| entry.name.eq_ignore_ascii_case(name))
+}
+
+fn entry_pattern(entry: &Entry) -> Pattern {
+    Pattern::from_rle(entry.rle).unwrap()
+}
+
+/// The library's names by apgcode, worked out on first use by running each
+/// object that repeats.
+fn names_by_code() -> &'static HashMap<String, &'static str> {
+    static NAMES: OnceLock<HashMap<String, &'static str>> = OnceLock::new();
+    NAMES.get_or_init(|| {
+        LIBRARY
+            .iter()
+            .filter(|entry| entry.kind != PatternKind::Gun)
+            .filter_map(|entry| {
+                let pattern = entry_pattern(entry);
+                let mut universe = Universe::empty(
+                    pattern.width() + 2 * PADDING,
+                    pattern.height() + 2 * PADDING,
+                    0,
+                );
+                universe.paste_pattern(&pattern, PADDING, PADDING);
+                let (motion, phases) = universe.find_motion(MAX_PERIOD)?;
+                Some((apgcode(&motion, &phases), entry.name))
+            })
+            .collect()
+    })
+}
+
+/// The name of the library object with `code`, if there is one. Codes are
+/// for B3/S23 only.
+pub(crate) fn name_for_code(code: &str) -> Option<&'static str> {
+    names_by_code().get(code).copied()
+}
+
+/// The built-in catalogue of named patterns.
+#[wasm_bindgen]
+pub struct PatternLibrary;
+
+#[wasm_bindgen]
+impl PatternLibrary {
+    /// Names of the library's patterns, optionally only those of `kind`.
+    pub fn names(kind: Option<PatternKind>) -> Vec<String> {
+        LIBRARY
+            .iter()
+            .filter(|entry| kind.is_none_or(|kind| entry.kind == kind))
+            .map(|entry| entry.name.to_string())
+            .collect()
+    }
+
+    pub fn kind(name: &str) -> Option<PatternKind> {
+        find(name).map(|entry| entry.kind)
+    }
+
+    /// The named pattern as an RLE file. Names are not case sensitive.
+    pub fn rle(name: &str) -> Option<String> {
+        find(name).map(|entry| entry_pattern(entry).to_rle(Rule::CONWAY))
+    }
+}
+
+impl PatternLibrary {
+    pub fn pattern(name: &str) -> Option<Pattern> {
+        find(name).map(entry_pattern)
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// Writes the named library pattern with its top-left corner at `(x, y)`,
+    /// wrapping around the edges, as a single undoable edit. Every cell of
+    /// the pattern's bounding box is overwritten.
+    pub fn stamp(&mut self, name: &str, x: u32, y: u32) -> Result<(), JsValue> {
+        let pattern = PatternLibrary::pattern(name)
+            .ok_or_else(|| JsValue::from_str(&format!("unknown pattern '{}'", name)))?;
+
+        self.write_pattern(&pattern, x, y, PasteMode::Copy);
+        Ok(())
+    }
+}
+
+impl Universe {
+    /// Copies `pattern` onto a board being set up directly, before
+    /// `recalculate`.
+    fn paste_pattern(&mut self, pattern: &Pattern, x: u32, y: u32) {
+        for py in 0..pattern.height() {
+            for px in 0..pattern.width() {
+                if pattern.get(px, py) == Cell::Alive {
+                    let idx = self.index(x + px, y + py);
+                    self.cells[idx] = Cell::Alive;
+                }
+            }
+        }
+        self.recalculate();
+    }
+}
@@ -1,9 +1,25 @@
 use getrandom::getrandom;
 use wasm_bindgen::prelude::*;
 
+use crate::access::AccessMode;
+use crate::activity::{Activity, RenderMode};
+use crate::brush::Brush;
+use crate::builder::{validate, UniverseBuilder};
+use crate::dirty::DirtyTiles;
+use crate::edit_history::EditHistory;
+use crate::grid::Grid;
+use crate::palette::{Palette, Theme};
+use crate::pattern::Pattern;
+use crate::period::CycleDetector;
+use crate::population::{BoundingBox, Population};
+use crate::rewind::Timeline;
+use crate::rule::Rule;
+use crate::stats::Stats;
+
 #[wasm_bindgen]
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Cell {
     Dead = 0,
     Alive = 1,
@@ -11,23 +27,38 @@ pub enum Cell {
 
 #[wasm_bindgen]
 pub struct Universe {
-    width: u32,
-    height: u32,
-    cell_size: u32,
-    cells: Vec<Cell>,
-    previous_cells: Vec<Cell>,
-    tick_count: u32,
-    canvas_width: u32,
-    canvas: Vec<u32>,
-    colour_canvas: Vec<u32>,
-    points: [(u32, u32); 8],
-    cell_neighbour_count: Vec<u8>,
-    previous_cell_neighbour_count: Vec<u8>,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) cell_size: u32,
+    pub(crate) cells: Vec<Cell>,
+    pub(crate) previous_cells: Vec<Cell>,
+    pub(crate) tick_count: u32,
+    pub(crate) canvas_width: u32,
+    pub(crate) canvas: Vec<u32>,
+    pub(crate) colour_canvas: Vec<u32>,
+    pub(crate) points: [(u32, u32); 8],
+    pub(crate) cell_neighbour_count: Vec<u8>,
+    pub(crate) previous_cell_neighbour_count: Vec<u8>,
+    pub(crate) rule: Rule,
+    pub(crate) edit_history: EditHistory,
+    pub(crate) timeline: Option<Timeline>,
+    pub(crate) population: Population,
+    pub(crate) cycles: CycleDetector,
+    pub(crate) activity: Activity,
+    pub(crate) render_mode: RenderMode,
+    pub(crate) stats: Stats,
+    pub(crate) selection: Option<BoundingBox>,
+    pub(crate) clipboard: Option<Pattern>,
+    pub(crate) brush: Brush,
+    pub(crate) access_mode: AccessMode,
+    pub(crate) palette: Palette,
+    pub(crate) grid: Grid,
+    pub(crate) dirty: DirtyTiles,
 }
 
 #[wasm_bindgen]
 impl Universe {
-    fn index(&self, x: u32, y: u32) -> usize {
+    pub(crate) fn index(&self, x: u32, y: u32) -> usize {
         (y * self.width + x) as usize
     }
 
@@ -49,108 +80,128 @@ impl Universe {
         }
     }
 
+    /// Reads the cell at `(x, y)`. Coordinates off the board are resolved by
+    /// the access mode, reading a dead cell if it rejects them.
     pub fn get(&self, x: u32, y: u32) -> Cell {
-        self.cells[self.index(x, y)]
+        match self.resolve(x as i64, y as i64) {
+            Ok((x, y)) => self.cells[self.index(x, y)],
+            Err(_) => Cell::Dead,
+        }
     }
 
+    /// Sets the cell at `(x, y)`. Coordinates off the board are resolved by
+    /// the access mode, doing nothing if it rejects them.
     pub fn set(&mut self, x: u32, y: u32, cell: Cell) {
+        if let Ok((x, y)) = self.resolve(x as i64, y as i64) {
+            self.set_resolved(x, y, cell);
+        }
+    }
+
+    /// Sets a cell known to be on the board, recording it in the edit
+    /// history.
+    pub(crate) fn set_resolved(&mut self, x: u32, y: u32, cell: Cell) {
+        let idx = self.index(x, y);
+        if self.edit_history.is_recording() && self.cells[idx] != cell {
+            self.edit_history.record(idx, self.cells[idx]);
+        }
+        self.set_cell(x, y, cell);
+    }
+
+    /// Sets a cell without recording it in the edit history.
+    pub(crate) fn set_cell(&mut self, x: u32, y: u32, cell: Cell) {
         let idx = self.index(x, y);
         if self.cells[idx] != cell {
             self.cells[idx] = cell;
-            let colour: u32;
+            self.population.cell_changed(x, y, cell);
+            self.cycles.cell_changed(idx);
+            self.cycles.interrupt();
+            self.activity.cell_changed(idx, self.tick_count);
+            if let Some(timeline) = &mut self.timeline {
+                timeline.cell_changed(self.tick_count, idx);
+            }
             if cell == Cell::Alive {
                 self.increment_neighbour_counts(x, y);
-                colour = self.colour_canvas[idx]
             } else {
                 self.decrement_neighbour_counts(x, y);
-                colour = 0xFF000000;
             }
-
-            for j in 0..self.cell_size {
-                let canvas_y = y * self.cell_size + j;
-                for i in 0..self.cell_size {
-                    let canvas_x = x * self.cell_size + i;
-                    let idx = canvas_y * self.canvas_width + canvas_x;
-
-                    self.canvas[idx as usize] = colour;
+            self.draw_cell(x, y);
+            if self.render_mode == RenderMode::Neighbours {
+                for (d_x, d_y) in self.points {
+                    self.draw_cell((x + d_x) % self.width, (y + d_y) % self.height);
                 }
             }
         }
     }
 
-    pub fn resize(&mut self, cell_size: u32) {
+    /// Paints the cell at `(x, y)` onto the canvas, inside any grid lines.
+    fn draw_cell(&mut self, x: u32, y: u32) {
+        self.dirty.mark(x, y);
+        let colour = self.cell_colour(self.index(x, y));
+        let inset = self.grid_inset();
+        for j in inset..self.cell_size {
+            let canvas_y = y * self.cell_size + j;
+            for i in inset..self.cell_size {
+                let canvas_x = x * self.cell_size + i;
+                let idx = canvas_y * self.canvas_width + canvas_x;
+
+                self.canvas[idx as usize] = colour;
+            }
+        }
+    }
+
+    /// Changes the pixels per cell and redraws the canvas, throwing a
+    /// `DimensionError` if the canvas would be too large.
+    pub fn resize(&mut self, cell_size: u32) -> Result<(), JsValue> {
+        validate(self.width, self.height, cell_size)?;
+        self.resize_canvas(cell_size);
+        Ok(())
+    }
+
+    /// Reallocates the canvas at `cell_size` pixels per cell and redraws
+    /// every cell.
+    pub(crate) fn resize_canvas(&mut self, cell_size: u32) {
         self.cell_size = cell_size;
         self.canvas_width = self.width * self.cell_size;
         self.canvas = vec![0xFFCCCCCC; (self.canvas_width * self.height * cell_size) as usize];
+        self.dirty.mark_all(self.width, self.height);
         for y in 0..self.height {
             for x in 0..self.width {
-                let idx = self.index(x, y);
-                let alive = self.cells[idx as usize] == Cell::Alive;
-                let colour = if alive {
-                    self.colour_canvas[idx]
-                } else {
-                    0xFF000000
-                };
-
-                for j in 0..self.cell_size {
-                    let canvas_y = y * self.cell_size + j;
-                    for i in 0..self.cell_size {
-                        let canvas_x = x * self.cell_size + i;
-                        let idx = canvas_y * self.canvas_width + canvas_x;
-
-                        self.canvas[idx as usize] = colour;
-                    }
-                }
+                self.draw_cell(x, y);
             }
         }
+        self.draw_grid();
+    }
+
+    pub fn tick_count(&self) -> u32 {
+        self.tick_count
+    }
+
+    pub fn rule(&self) -> String {
+        self.rule.to_string()
+    }
+
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), JsValue> {
+        self.rule = rule
+            .parse()
+            .map_err(|err: String| JsValue::from_str(&err))?;
+        Ok(())
     }
 }
 
 #[wasm_bindgen]
 impl Universe {
+    /// Creates a randomly filled universe. Panics if the dimensions are
+    /// invalid, which `UniverseBuilder` reports as an error instead.
     pub fn new(width: u32, height: u32, cell_size: u32) -> Universe {
-        let cells = vec![Cell::Dead; (width * height) as usize];
-        let previous_cells = vec![Cell::Dead; (width * height) as usize];
-        let canvas = vec![0xFFCCCCCC; (width * height * cell_size * cell_size) as usize];
-        let colour_canvas = vec![0xFFCCCCCC; (width * height) as usize];
-
-        let points = [
-            (width - 1, height - 1),
-            (width - 1, 0),
-            (width - 1, 1),
-            (0, height - 1),
-            (0, 1),
-            (1, height - 1),
-            (1, 0),
-            (1, 1),
-        ];
-
-        let cell_neighbour_count = vec![0u8; (width * height) as usize];
-        let previous_cell_neighbour_count = vec![0u8; (width * height) as usize];
-
-        let mut universe = Universe {
-            width,
-            height,
-            cells,
-            previous_cells,
-            tick_count: 0,
-            cell_size,
-            canvas_width: width * cell_size,
-            canvas,
-            colour_canvas,
-            points,
-            cell_neighbour_count,
-            previous_cell_neighbour_count,
-        };
-
-        universe.init();
-
-        universe
+        UniverseBuilder::new(width, height)
+            .cell_size(cell_size)
+            .try_build()
+            .unwrap_or_else(|err| panic!("{}", err))
     }
 
-    pub(self) fn init(&mut self) {
-        // Get random bytes
-        let mut random = vec![0; (self.width * self.height / 8) as usize];
+    pub(crate) fn init(&mut self) {
+        // Get random bytes, one bit per cell
+        let mut random = vec![0; self.cells.len().div_ceil(8)];
         getrandom(&mut random).unwrap_or_else(|err| println!("{:?}", err));
 
         std::mem::swap(
@@ -166,6 +217,8 @@ impl Universe {
                 self.increment_neighbour_counts(i % self.width, i / self.width);
             }
         }
+        self.recount_population();
+        self.cycles.reset(&self.cells);
 
         // Initialise canvas and colour_canvas
         self.canvas_width = self.width * self.cell_size;
@@ -173,29 +226,14 @@ impl Universe {
             for x in 0..self.width {
                 let idx = self.index(x, y);
 
-                let red = y * 0xFF / self.height;
-                let green = ((self.width - x) * 0xB0 / self.width) << 8;
-                let blue = (x * 0xFF / self.width) << 16;
-                let colour = 0xFF000000 | red | green | blue;
-                self.colour_canvas[idx] = colour;
-
-                let alive = self.cells[idx as usize] == Cell::Alive;
-                let cell_colour = if alive { colour } else { 0xFF000000 };
-
-                for j in 0..self.cell_size {
-                    let canvas_y = y * self.cell_size + j;
-                    for i in 0..self.cell_size {
-                        let canvas_x = x * self.cell_size + i;
-                        let idx = canvas_y * self.canvas_width + canvas_x;
-
-                        self.canvas[idx as usize] = cell_colour;
-                    }
-                }
+                self.colour_canvas[idx] = self.gradient_colour(x, y);
+                self.draw_cell(x, y);
             }
         }
     }
 
     pub fn tick(&mut self) {
+        self.edit_history.clear();
         std::mem::swap(&mut self.cells, &mut self.previous_cells);
         std::mem::swap(
             &mut self.cell_neighbour_count,
@@ -205,6 +243,11 @@ impl Universe {
         for i in 0..self.cell_neighbour_count.len() {
             self.cell_neighbour_count[i] = 0;
         }
+        // Advanced up front so cells are drawn at their new ages.
+        self.tick_count += 1;
+        let generation = self.tick_count;
+        // Every live cell ages, so each one is redrawn when colouring by age.
+        let redraw_alive = self.render_mode == RenderMode::Age;
 
         for y in 0..self.height {
             for x in 0..self.width {
@@ -212,12 +255,7 @@ impl Universe {
                 let cell = self.previous_cells[idx];
                 let live_neighbours = self.previous_cell_neighbour_count[idx];
 
-                let next_cell = match (cell, live_neighbours) {
-                    (Cell::Alive, 2) | (Cell::Alive, 3) => Cell::Alive,
-                    (Cell::Alive, _) => Cell::Dead,
-                    (Cell::Dead, 3) => Cell::Alive,
-                    (_, _) => cell,
-                };
+                let next_cell = self.rule.next(cell, live_neighbours);
 
                 self.cells[idx] = next_cell;
                 if next_cell == Cell::Alive {
@@ -225,29 +263,167 @@ impl Universe {
                 }
 
                 if next_cell != cell {
-                    let colour = if next_cell == Cell::Alive {
-                        self.colour_canvas[idx]
-                    } else {
-                        0xFF000000
-                    };
-
-                    for j in 0..self.cell_size {
-                        let canvas_y = y * self.cell_size + j;
-                        for i in 0..self.cell_size {
-                            let canvas_x = x * self.cell_size + i;
-                            let idx = canvas_y * self.canvas_width + canvas_x;
-
-                            self.canvas[idx as usize] = colour;
-                        }
+                    self.population.cell_changed(x, y, next_cell);
+                    self.cycles.cell_changed(idx);
+                    self.activity.cell_changed(idx, generation);
+                    self.stats.cell_changed(next_cell);
+                    self.draw_cell(x, y);
+                } else if redraw_alive && next_cell == Cell::Alive {
+                    self.draw_cell(x, y);
+                }
+            }
+        }
+
+        // Neighbour counts are only complete once every cell has ticked.
+        if self.render_mode == RenderMode::Neighbours {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    if self.cells[self.index(x, y)] == Cell::Alive {
+                        self.draw_cell(x, y);
                     }
                 }
             }
         }
 
-        self.tick_count += 1;
+        self.population.record(self.tick_count);
+        self.cycles.record(self.tick_count);
+        if self.stats.is_enabled() {
+            self.record_stats();
+        }
+
+        if let Some(timeline) = &mut self.timeline {
+            timeline.record(self.tick_count, &self.cells);
+        }
     }
 
     pub fn canvas(&mut self) -> *const u32 {
         self.canvas.as_ptr()
     }
 }
+
+impl Universe {
+    /// Creates a universe with every cell dead.
+    pub(crate) fn empty(width: u32, height: u32, cell_size: u32) -> Universe {
+        let cells = vec![Cell::Dead; (width * height) as usize];
+        let previous_cells = vec![Cell::Dead; (width * height) as usize];
+        let colour_canvas = vec![0xFFCCCCCC; (width * height) as usize];
+
+        let points = Universe::neighbour_offsets(width, height);
+
+        let cell_neighbour_count = vec![0u8; (width * height) as usize];
+        let previous_cell_neighbour_count = vec![0u8; (width * height) as usize];
+
+        let mut universe = Universe {
+            width,
+            height,
+            cells,
+            previous_cells,
+            tick_count: 0,
+            cell_size,
+            canvas_width: width * cell_size,
+            canvas: Vec::new(),
+            colour_canvas,
+            points,
+            cell_neighbour_count,
+            previous_cell_neighbour_count,
+            rule: Rule::CONWAY,
+            edit_history: EditHistory::new(),
+            timeline: None,
+            population: Population::new(),
+            cycles: CycleDetector::new(),
+            activity: Activity::new((width * height) as usize, 0),
+            render_mode: RenderMode::Colour,
+            stats: Stats::new(),
+            selection: None,
+            clipboard: None,
+            brush: Brush::new(),
+            access_mode: AccessMode::Wrap,
+            palette: Palette::new(Theme::Dark),
+            grid: Grid::new(),
+            dirty: DirtyTiles::new(),
+        };
+
+        universe.reset_colour_canvas();
+        universe.resize_canvas(cell_size);
+
+        universe
+    }
+
+    /// Copies the cells and rule into a universe without a canvas, so
+    /// analyses can run it forward without disturbing this one.
+    pub(crate) fn headless_copy(&self) -> Universe {
+        let mut universe = Universe::empty(self.width, self.height, 0);
+        universe.rule = self.rule;
+        universe.tick_count = self.tick_count;
+        universe.cells.copy_from_slice(&self.cells);
+        universe.recalculate();
+        universe
+    }
+
+    fn neighbour_offsets(width: u32, height: u32) -> [(u32, u32); 8] {
+        [
+            (width - 1, height - 1),
+            (width - 1, 0),
+            (width - 1, 1),
+            (0, height - 1),
+            (0, 1),
+            (1, height - 1),
+            (1, 0),
+            (1, 1),
+        ]
+    }
+
+    fn gradient_colour(&self, x: u32, y: u32) -> u32 {
+        let red = y * 0xFF / self.height;
+        let green = ((self.width - x) * 0xB0 / self.width) << 8;
+        let blue = (x * 0xFF / self.width) << 16;
+        0xFF000000 | red | green | blue
+    }
+
+    /// Regenerates the default rainbow colour grid for the current size.
+    pub(crate) fn reset_colour_canvas(&mut self) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let idx = self.index(x, y);
+                self.colour_canvas[idx] = self.gradient_colour(x, y);
+            }
+        }
+    }
+
+    /// Resizes every per-cell buffer for a new world size, leaving all cells
+    /// dead. Callers are expected to fill the cells and `recalculate`.
+    pub(crate) fn reallocate(&mut self, width: u32, height: u32) {
+        let len = (width * height) as usize;
+        self.width = width;
+        self.height = height;
+        self.points = Universe::neighbour_offsets(width, height);
+        self.cells = vec![Cell::Dead; len];
+        self.previous_cells = vec![Cell::Dead; len];
+        self.colour_canvas = vec![0xFFCCCCCC; len];
+        self.cell_neighbour_count = vec![0u8; len];
+        self.previous_cell_neighbour_count = vec![0u8; len];
+        self.activity.reset(len, self.tick_count);
+        self.selection = None;
+    }
+
+    /// Rebuilds the neighbour counts and canvas after `cells` has been
+    /// replaced wholesale. Ages and change counts start again from here.
+    pub(crate) fn recalculate(&mut self) {
+        for count in self.cell_neighbour_count.iter_mut() {
+            *count = 0;
+        }
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.cells[self.index(x, y)] == Cell::Alive {
+                    self.increment_neighbour_counts(x, y);
+                }
+            }
+        }
+
+        self.recount_population();
+        self.cycles.reset(&self.cells);
+        self.activity.reset(self.cells.len(), self.tick_count);
+        self.stats.truncate(self.tick_count);
+        self.resize_canvas(self.cell_size);
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use wasm_bindgen::prelude::*;
+
+use crate::life_counter_combined::{Cell, Universe};
+
+/// How a pattern repeats: every `period` generations, moved by `(dx, dy)`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Motion {
+    pub period: u32,
+    pub dx: i32,
+    pub dy: i32,
+}
+
+#[wasm_bindgen]
+impl Motion {
+    pub fn is_spaceship(&self) -> bool {
+        self.dx != 0 || self.dy != 0
+    }
+
+    /// The speed in the usual notation, e.g. `c/4 diagonal`, `2c/5
+    /// orthogonal` or `(2,1)c/6 oblique`.
+    pub fn speed(&self) -> String {
+        let (a, b) = (self.dx.unsigned_abs(), self.dy.unsigned_abs());
+        let (major, minor) = (a.max(b), a.min(b));
+        if major == 0 {
+            return "stationary".to_string();
+        }
+        if minor != 0 && minor != major {
+            return format!("({},{})c/{} oblique", major, minor, self.period);
+        }
+
+        // Orthogonal and diagonal speeds are written as a reduced fraction,
+        // so the p4 LWSS moving 2 cells is c/2.
+        let divisor = gcd(major, self.period);
+        let (distance, period) = (major / divisor, self.period / divisor);
+        let c = if distance == 1 {
+            "c".to_string()
+        } else {
+            format!("{}c", distance)
+        };
+        let direction = if minor == 0 { "orthogonal" } else { "diagonal" };
+        format!("{}/{} {}", c, period, direction)
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Live cells relative to the corner of their bounding box. On a torus the
+/// box starts just after the widest run of empty columns (and rows), so an
+/// object straddling an edge is kept in one piece.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Normalised {
+    pub(crate) x: u32,
+    pub(crate) y: u32,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    // Sorted by row, then column.
+    pub(crate) cells: Vec<(u32, u32)>,
+    pub(crate) hash: u64,
+}
+
+pub(crate) fn normalise(cells: &[Cell], width: u32, height: u32) -> Option<Normalised> {
+    let mut columns = vec![false; width as usize];
+    let mut rows = vec![false; height as usize];
+    for (idx, _) in cells.iter().enumerate().filter(|(_, &c)| c == Cell::Alive) {
+        columns[idx % width as usize] = true;
+        rows[idx / width as usize] = true;
+    }
+
+    let (x, span_x) = circular_extent(&columns)?;
+    let (y, span_y) = circular_extent(&rows)?;
+
+    let mut offsets: Vec<(u32, u32)> = cells
+        .iter()
+        .enumerate()
+        .filter(|(_, &c)| c == Cell::Alive)
+        .map(|(idx, _)| {
+            let cx = idx as u32 % width;
+            let cy = idx as u32 / width;
+            ((cx + width - x) % width, (cy + height - y) % height)
+        })
+        .collect();
+    offsets.sort_unstable_by_key(|&(cx, cy)| (cy, cx));
+
+    let mut hasher = DefaultHasher::new();
+    offsets.hash(&mut hasher);
+
+    Some(Normalised {
+        x,
+        y,
+        width: span_x,
+        height: span_y,
+        cells: offsets,
+        hash: hasher.finish(),
+    })
+}
+
+// Returns the start and length of the occupied span, wrapping around, or
+// `None` if nothing is occupied.
+fn circular_extent(occupied: &[bool]) -> Option<(u32, u32)> {
+    let n = occupied.len();
+    let first = occupied.iter().position(|&o| o)?;
+
+    let mut best_gap = 0;
+    let mut best_start = first;
+    let mut gap = 0;
+    for step in 1..=n {
+        let i = (first + step) % n;
+        if occupied[i] {
+            if gap > best_gap {
+                best_gap = gap;
+                best_start = i;
+            }
+            gap = 0;
+        } else {
+            gap += 1;
+        }
+    }
+
+    Some((best_start as u32, (n - best_gap) as u32))
+}
+
+/// Wraps a coordinate difference on a ring of size `n` into `(-n/2, n/2]`.
+pub(crate) fn wrapped_offset(to: u32, from: u32, n: u32) -> i32 {
+    let d = (to + n - from) % n;
+    if d > n / 2 {
+        d as i32 - n as i32
+    } else {
+        d as i32
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// Runs a copy of the board for up to `max_period` generations, looking
+    /// for the first one where the live cells reappear, possibly translated.
+    /// Intended for a single isolated object; wrapping around the edges is
+    /// taken into account.
+    pub fn detect_motion(&self, max_period: u32) -> Option<Motion> {
+        self.find_motion(max_period).map(|(motion, _)| motion)
+    }
+}
+
+impl Universe {
+    /// Like `detect_motion`, also returning each phase of the pattern.
+    pub(crate) fn find_motion(&self, max_period: u32) -> Option<(Motion, Vec<Normalised>)> {
+        let start = normalise(&self.cells, self.width, self.height)?;
+        let mut phases = vec![start.clone()];
+
+        let mut universe = self.headless_copy();
+        for period in 1..=max_period {
+            universe.tick();
+            let current = normalise(&universe.cells, self.width, self.height)?;
+
+            if current.hash == start.hash && current.cells == start.cells {
+                let motion = Motion {
+                    period,
+                    dx: wrapped_offset(current.x, start.x, self.width),
+                    dy: wrapped_offset(current.y, start.y, self.height),
+                };
+                return Some((motion, phases));
+            }
+            phases.push(current);
+        }
+
+        None
+    }
+}
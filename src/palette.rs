@@ -0,0 +1,211 @@
+use wasm_bindgen::prelude::*;
+
+use crate::activity::RenderMode;
+use crate::life_counter_combined::{Cell, Universe};
+
+// Colours are canvas pixels, 0xAABBGGRR, so the bytes land in RGBA order.
+const BLACK: u32 = 0xFF000000;
+const WHITE: u32 = 0xFFFFFFFF;
+const RED: u32 = 0xFF0000FF;
+const YELLOW: u32 = 0xFF00FFFF;
+const GREEN: u32 = 0xFF00FF00;
+const CYAN: u32 = 0xFFFFFF00;
+const BLUE: u32 = 0xFFFF0000;
+
+// Ages and change counts at or beyond these are drawn at the end of their
+// ramps.
+const AGE_SCALE: u32 = 256;
+const HEAT_SCALE: u32 = 1024;
+
+/// A set of default colours for everything but the colour grid.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Theme {
+    /// Bright cells on black.
+    Dark = 0,
+    /// Dark cells on white.
+    Light = 1,
+}
+
+/// A colour grid spread over the board.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GradientPreset {
+    /// Red down the board, green to blue across it. The default.
+    Rainbow = 0,
+    /// Deep red in the top-left corner to yellow in the bottom-right.
+    Fire = 1,
+    /// Navy in the top-left corner to pale cyan in the bottom-right.
+    Ocean = 2,
+    /// Dark grey in the top-left corner to white in the bottom-right.
+    Greyscale = 3,
+}
+
+/// The colours cells are drawn in, apart from the per-cell colour grid.
+pub(crate) struct Palette {
+    theme: Theme,
+    dead: u32,
+    // Ramps run through their stops evenly, from the first to the last.
+    age: Vec<u32>,
+    heat: Vec<u32>,
+    neighbours: [u32; 9],
+}
+
+impl Palette {
+    pub(crate) fn new(theme: Theme) -> Palette {
+        let neighbours = [BLUE, CYAN, GREEN, YELLOW, RED];
+        let mut palette = Palette {
+            theme,
+            dead: BLACK,
+            age: vec![WHITE, 0xFF993300],
+            heat: vec![BLACK, RED, YELLOW, WHITE],
+            neighbours: [0; 9],
+        };
+        if theme == Theme::Light {
+            palette.dead = WHITE;
+            palette.age = vec![BLACK, 0xFFFFCC99];
+            palette.heat = vec![WHITE, YELLOW, RED, BLACK];
+        }
+        for (count, colour) in palette.neighbours.iter_mut().enumerate() {
+            *colour = ramp(&neighbours, count as f64 / 8.0);
+        }
+        palette
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    pub fn theme(&self) -> Theme {
+        self.palette.theme
+    }
+
+    /// Switches to the theme's dead colour, ramps and neighbour colours,
+    /// leaving the colour grid alone, and redraws the canvas.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.palette = Palette::new(theme);
+        self.resize_canvas(self.cell_size);
+    }
+
+    pub fn dead_colour(&self) -> u32 {
+        self.palette.dead
+    }
+
+    /// Sets the colour of dead cells, as a canvas pixel `0xAABBGGRR`.
+    pub fn set_dead_colour(&mut self, colour: u32) {
+        self.palette.dead = colour;
+        self.resize_canvas(self.cell_size);
+    }
+
+    /// Colours every cell of the colour grid the same.
+    pub fn set_alive_colour(&mut self, colour: u32) {
+        self.colour_canvas.fill(colour);
+        self.resize_canvas(self.cell_size);
+    }
+
+    /// Fills the colour grid with a preset gradient.
+    pub fn set_gradient(&mut self, preset: GradientPreset) {
+        let stops: &[u32] = match preset {
+            GradientPreset::Rainbow => {
+                self.reset_colour_canvas();
+                self.resize_canvas(self.cell_size);
+                return;
+            }
+            GradientPreset::Fire => &[0xFF000080, RED, 0xFF0080FF, YELLOW],
+            GradientPreset::Ocean => &[0xFF400000, BLUE, CYAN, 0xFFFFFFE0],
+            GradientPreset::Greyscale => &[0xFF404040, WHITE],
+        };
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let t = (x as f64 / self.width as f64 + y as f64 / self.height as f64) / 2.0;
+                let idx = self.index(x, y);
+                self.colour_canvas[idx] = ramp(stops, t);
+            }
+        }
+        self.resize_canvas(self.cell_size);
+    }
+
+    /// The colours `mode` draws with: the colour grid row by row for
+    /// `Colour`, the ramp stops for `Age` and `Activity`, or the colours for
+    /// zero to eight neighbours for `Neighbours`.
+    pub fn colour_table(&self, mode: RenderMode) -> Vec<u32> {
+        match mode {
+            RenderMode::Colour => self.colour_canvas.clone(),
+            RenderMode::Age => self.palette.age.clone(),
+            RenderMode::Activity => self.palette.heat.clone(),
+            RenderMode::Neighbours => self.palette.neighbours.to_vec(),
+        }
+    }
+
+    /// Replaces the colours `mode` draws with, laid out as by
+    /// `colour_table`, and redraws the canvas. Ramps need at least one stop.
+    pub fn set_colour_table(&mut self, mode: RenderMode, colours: &[u32]) -> Result<(), JsValue> {
+        let expected = match mode {
+            RenderMode::Colour => Some(self.colour_canvas.len()),
+            RenderMode::Neighbours => Some(self.palette.neighbours.len()),
+            RenderMode::Age | RenderMode::Activity => None,
+        };
+        match expected {
+            Some(expected) if colours.len() != expected => {
+                return Err(JsValue::from_str(&format!(
+                    "expected {} colours, got {}",
+                    expected,
+                    colours.len()
+                )))
+            }
+            None if colours.is_empty() => {
+                return Err(JsValue::from_str("a colour ramp needs at least one colour"))
+            }
+            _ => {}
+        }
+
+        match mode {
+            RenderMode::Colour => self.colour_canvas.copy_from_slice(colours),
+            RenderMode::Age => self.palette.age = colours.to_vec(),
+            RenderMode::Activity => self.palette.heat = colours.to_vec(),
+            RenderMode::Neighbours => self.palette.neighbours.copy_from_slice(colours),
+        }
+        self.resize_canvas(self.cell_size);
+        Ok(())
+    }
+}
+
+impl Universe {
+    /// The canvas colour of the cell at `idx` under the current render mode.
+    pub(crate) fn cell_colour(&self, idx: usize) -> u32 {
+        let alive = self.cells[idx] == Cell::Alive;
+        let palette = &self.palette;
+        match self.render_mode {
+            RenderMode::Colour if alive => self.colour_canvas[idx],
+            RenderMode::Age if alive => ramp(&palette.age, intensity(self.age(idx), AGE_SCALE)),
+            RenderMode::Activity => match self.activity.changes[idx] {
+                0 => palette.dead,
+                changes => ramp(&palette.heat, intensity(changes, HEAT_SCALE)),
+            },
+            RenderMode::Neighbours if alive => {
+                palette.neighbours[self.cell_neighbour_count[idx] as usize]
+            }
+            _ => palette.dead,
+        }
+    }
+}
+
+/// How far `value` is along a logarithmic scale reaching 1 at `scale`.
+fn intensity(value: u32, scale: u32) -> f64 {
+    (((value as f64) + 1.0).ln() / ((scale as f64) + 1.0).ln()).min(1.0)
+}
+
+/// The colour a fraction `t` of the way along evenly spaced `stops`.
+fn ramp(stops: &[u32], t: f64) -> u32 {
+    let position = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+    let i = (position as usize).min(stops.len() - 1);
+    let (from, to) = (stops[i], stops[(i + 1).min(stops.len() - 1)]);
+    let fraction = position - i as f64;
+
+    (0..4).fold(0, |colour, byte| {
+        let shift = byte * 8;
+        let a = (from >> shift & 0xFF) as f64;
+        let b = (to >> shift & 0xFF) as f64;
+        let channel = (a + (b - a) * fraction).round() as u32;
+        colour | channel << shift
+    })
+}
@@ -0,0 +1,306 @@
+use crate::builder::MAX_CELLS;
+use crate::life_counter_combined::Cell;
+use crate::rule::Rule;
+
+/// A rectangular block of cells, detached from any universe.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    width: u32,
+    height: u32,
+    cells: Vec<Cell>,
+}
+
+impl Pattern {
+    pub fn new(width: u32, height: u32, cells: Vec<Cell>) -> Pattern {
+        assert_eq!(cells.len(), width as usize * height as usize);
+        Pattern {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    pub fn empty(width: u32, height: u32) -> Pattern {
+        Pattern::new(
+            width,
+            height,
+            vec![Cell::Dead; width as usize * height as usize],
+        )
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    pub fn into_cells(self) -> Vec<Cell> {
+        self.cells
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Cell {
+        self.cells[(y * self.width + x) as usize]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, cell: Cell) {
+        self.cells[(y * self.width + x) as usize] = cell;
+    }
+
+    /// Encodes the pattern as an RLE file with an `x = .., y = ..` header.
+    pub fn to_rle(&self, rule: Rule) -> String {
+        format!(
+            "x = {}, y = {}, rule = {}\n{}\n",
+            self.width,
+            self.height,
+            rule,
+            self.rle_body()
+        )
+    }
+
+    /// Encodes just the run-length body of the pattern, terminated by `!`.
+    pub fn rle_body(&self) -> String {
+        let mut tokens = Vec::new();
+        let mut pending_rows = 0;
+
+        for y in 0..self.height {
+            let row = &self.cells[(y * self.width) as usize..((y + 1) * self.width) as usize];
+            let end = match row.iter().rposition(|&cell| cell == Cell::Alive) {
+                Some(end) => end + 1,
+                None => {
+                    pending_rows += 1;
+                    continue;
+                }
+            };
+
+            // Blank rows before the first live one are written too, so the
+            // pattern keeps its place in the box.
+            if !tokens.is_empty() {
+                pending_rows += 1;
+            }
+            if pending_rows > 0 {
+                tokens.push(run_token(pending_rows, '$'));
+            }
+            pending_rows = 0;
+
+            let mut x = 0;
+            while x < end {
+                let cell = row[x];
+                let run = row[x..end].iter().take_while(|&&c| c == cell).count();
+                let tag = if cell == Cell::Alive { 'o' } else { 'b' };
+                tokens.push(run_token(run, tag));
+                x += run;
+            }
+        }
+        tokens.push("!".to_string());
+
+        // Wrap lines at 70 characters without splitting a token.
+        let mut body = String::new();
+        let mut line_len = 0;
+        for token in tokens {
+            if line_len + token.len() > 70 {
+                body.push('\n');
+                line_len = 0;
+            }
+            line_len += token.len();
+            body.push_str(&token);
+        }
+        body
+    }
+
+    /// Parses an RLE file. Comment lines starting with `#` are skipped, and
+    /// the size is taken from the header when present.
+    pub fn from_rle(rle: &str) -> Result<Pattern, String> {
+        let mut size = None;
+        let mut body = String::new();
+
+        for line in rle.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if size.is_none() && body.is_empty() && line.starts_with('x') {
+                size = Some(parse_header(line)?);
+                continue;
+            }
+            body.push_str(line);
+        }
+
+        let (runs, body_size) = parse_body(&body, size)?;
+        let (width, height) = size.unwrap_or(body_size);
+        if width as u64 * height as u64 > MAX_CELLS {
+            return Err(format!(
+                "a {}x{} RLE pattern has more than the {} cells allowed",
+                width, height, MAX_CELLS
+            ));
+        }
+
+        let mut pattern = Pattern::empty(width, height);
+        for (x, y, run) in runs {
+            for x in x..x + run {
+                pattern.set(x, y, Cell::Alive);
+            }
+        }
+        Ok(pattern)
+    }
+
+    /// Parses a bare RLE body into a pattern of known size.
+    pub fn from_rle_body(width: u32, height: u32, body: &str) -> Result<Pattern, String> {
+        let mut rle = format!("x = {}, y = {}\n", width, height);
+        rle.push_str(body);
+        Pattern::from_rle(&rle)
+    }
+}
+
+fn run_token(run: usize, tag: char) -> String {
+    if run == 1 {
+        tag.to_string()
+    } else {
+        format!("{}{}", run, tag)
+    }
+}
+
+fn parse_header(line: &str) -> Result<(u32, u32), String> {
+    let mut width = None;
+    let mut height = None;
+
+    for field in line.split(',') {
+        let (key, value) = match field.split_once('=') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        let value = value.trim();
+        match key.trim() {
+            "x" => width = value.parse().ok(),
+            "y" => height = value.parse().ok(),
+            _ => {}
+        }
+    }
+
+    match (width, height) {
+        (Some(width), Some(height)) => Ok((width, height)),
+        _ => Err(format!("invalid RLE header '{}'", line)),
+    }
+}
+
+/// A horizontal run of live cells, as `(x, y, length)`.
+type Run = (u32, u32, u32);
+
+/// Parses an RLE body into its live runs and the size of the box it fills.
+/// Runs reaching outside `size`, or outside any box of `MAX_CELLS` cells when
+/// there is no header, are rejected as they are read.
+fn parse_body(body: &str, size: Option<(u32, u32)>) -> Result<(Vec<Run>, (u32, u32)), String> {
+    let (max_width, max_height) = size
+        .map(|(width, height)| (width as u64, height as u64))
+        .unwrap_or((MAX_CELLS, MAX_CELLS));
+    let too_large = || match size {
+        Some((width, height)) => {
+            format!("RLE pattern does not fit its {}x{} header", width, height)
+        }
+        None => format!("RLE pattern has more than the {} cells allowed", MAX_CELLS),
+    };
+
+    let mut runs = Vec::new();
+    let (mut x, mut y) = (0u64, 0u64);
+    let (mut width, mut height) = (0u64, 0u64);
+    let mut count: Option<u64> = None;
+
+    for c in body.chars() {
+        match c {
+            '0'..='9' => {
+                let digit = c.to_digit(10).unwrap() as u64;
+                count = Some(
+                    count
+                        .unwrap_or(0)
+                        .checked_mul(10)
+                        .and_then(|n| n.checked_add(digit))
+                        .ok_or("RLE run count is too large")?,
+                );
+            }
+            'b' | '.' | 'o' | 'A' => {
+                let run = count.take().unwrap_or(1);
+                if run == 0 {
+                    continue;
+                }
+                if run > max_width - x || y >= max_height || (x + run) * (y + 1) > MAX_CELLS {
+                    return Err(too_large());
+                }
+                if c == 'o' || c == 'A' {
+                    runs.push((x as u32, y as u32, run as u32));
+                }
+                x += run;
+                width = width.max(x);
+                height = y + 1;
+            }
+            '$' => {
+                y = y.saturating_add(count.take().unwrap_or(1));
+                x = 0;
+            }
+            '!' => break,
+            c if c.is_whitespace() => {}
+            c => return Err(format!("unexpected character '{}' in RLE", c)),
+        }
+    }
+
+    Ok((runs, (width as u32, height as u32)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leading_blank_rows_and_columns() {
+        let mut pattern = Pattern::empty(8, 9);
+        pattern.set(3, 5, Cell::Alive);
+        pattern.set(4, 7, Cell::Alive);
+        pattern.set(5, 7, Cell::Alive);
+
+        assert_eq!(pattern.rle_body(), "5$3bo2$4b2o!");
+        assert_eq!(
+            Pattern::from_rle_body(8, 9, &pattern.rle_body()),
+            Ok(pattern.clone())
+        );
+        let rle = pattern.to_rle(Rule::default());
+        assert_eq!(Pattern::from_rle(&rle), Ok(pattern));
+    }
+
+    #[test]
+    fn blank_pattern() {
+        let pattern = Pattern::empty(4, 3);
+        assert_eq!(pattern.rle_body(), "!");
+        assert_eq!(Pattern::from_rle_body(4, 3, "!"), Ok(pattern));
+    }
+
+    #[test]
+    fn size_without_header() {
+        let pattern = Pattern::from_rle("bo$2bo$3o!").unwrap();
+        assert_eq!((pattern.width(), pattern.height()), (3, 3));
+        assert_eq!(pattern.get(1, 0), Cell::Alive);
+        assert_eq!(pattern.get(0, 0), Cell::Dead);
+    }
+
+    #[test]
+    fn runs_outside_the_header() {
+        assert!(Pattern::from_rle("x = 3, y = 3\n4o!").is_err());
+        assert!(Pattern::from_rle("x = 3, y = 3\n3$o!").is_err());
+        assert!(Pattern::from_rle("x = 3, y = 3\n4000000000o!").is_err());
+        assert!(Pattern::from_rle("x = 3, y = 3\n99999999999999999999o!").is_err());
+        // Blank rows past the end are harmless.
+        assert!(Pattern::from_rle("x = 3, y = 3\n3o5$!").is_ok());
+    }
+
+    #[test]
+    fn oversized() {
+        assert!(Pattern::from_rle("x = 100000, y = 100000\no!").is_err());
+        assert!(Pattern::from_rle("x = 4294967295, y = 4294967295\no!").is_err());
+        assert!(Pattern::from_rle("4000000000o!").is_err());
+        assert!(Pattern::from_rle("4000000000$o!").is_err());
+        assert!(Pattern::from_rle("10000o100000$o!").is_err());
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::{HashMap, VecDeque};
+
+use wasm_bindgen::prelude::*;
+
+use crate::life_counter_combined::{Cell, Universe};
+use crate::utils::SplitMix64;
+
+const DEFAULT_WINDOW: usize = 1000;
+
+/// The board repeats every `period` generations, first doing so from
+/// `start_generation`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Periodicity {
+    pub period: u32,
+    pub start_generation: u32,
+}
+
+/// Zobrist key of a cell, derived from its index with SplitMix64 so no key
+/// table needs to be stored.
+#[inline]
+pub(crate) fn zobrist(idx: usize) -> u64 {
+    SplitMix64::new(idx as u64).next_u64()
+}
+
+/// Detects when the whole board becomes periodic.
+///
+/// A Zobrist hash of the board is updated as cells change, and the hashes of
+/// the last `window` generations are kept to find the first repeat.
+pub(crate) struct CycleDetector {
+    pub(crate) hash: u64,
+    recent: VecDeque<(u64, u32)>,
+    // Most recent generation with each hash in `recent`.
+    seen: HashMap<u64, u32>,
+    window: usize,
+    cycle: Option<Periodicity>,
+}
+
+impl CycleDetector {
+    pub(crate) fn new() -> CycleDetector {
+        CycleDetector {
+            hash: 0,
+            recent: VecDeque::new(),
+            seen: HashMap::new(),
+            window: DEFAULT_WINDOW,
+            cycle: None,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn cell_changed(&mut self, idx: usize) {
+        self.hash ^= zobrist(idx);
+    }
+
+    /// Forgets every recorded generation and rehashes `cells`.
+    pub(crate) fn reset(&mut self, cells: &[Cell]) {
+        self.hash = cells
+            .iter()
+            .enumerate()
+            .filter(|(_, &cell)| cell == Cell::Alive)
+            .fold(0, |hash, (idx, _)| hash ^ zobrist(idx));
+        self.recent.clear();
+        self.seen.clear();
+        self.cycle = None;
+    }
+
+    /// The board was edited outside of a tick, so it is no longer known to
+    /// be cycling.
+    pub(crate) fn interrupt(&mut self) {
+        self.cycle = None;
+    }
+
+    pub(crate) fn cycle(&self) -> Option<Periodicity> {
+        self.cycle
+    }
+
+    /// Records the current hash as `generation`.
+    pub(crate) fn record(&mut self, generation: u32) {
+        if let Some(&(hash, last)) = self.recent.back() {
+            if last == generation {
+                // Re-recording after edits, drop the stale entry.
+                self.recent.pop_back();
+                if self.seen.get(&hash) == Some(&generation) {
+                    self.seen.remove(&hash);
+                }
+            }
+        }
+
+        match self.seen.get(&self.hash) {
+            Some(&previous) if previous < generation => {
+                let period = generation - previous;
+                if self.cycle.map(|cycle| cycle.period) != Some(period) {
+                    self.cycle = Some(Periodicity {
+                        period,
+                        start_generation: previous,
+                    });
+                }
+            }
+            _ => self.cycle = None,
+        }
+
+        self.recent.push_back((self.hash, generation));
+        self.seen.insert(self.hash, generation);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.recent.len() > self.window {
+            let (hash, old) = self.recent.pop_front().unwrap();
+            if self.seen.get(&hash) == Some(&old) {
+                self.seen.remove(&hash);
+            }
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// The board's current period, if it has repeated within the cycle
+    /// window. Detection relies on a 64-bit hash, so collisions are possible
+    /// but vanishingly unlikely.
+    pub fn periodicity(&self) -> Option<Periodicity> {
+        self.cycles.cycle()
+    }
+
+    /// A Zobrist hash of the live cells.
+    pub fn board_hash(&self) -> u64 {
+        self.cycles.hash
+    }
+
+    /// Sets how many past generations are remembered, which bounds the
+    /// longest detectable period.
+    pub fn set_cycle_window(&mut self, generations: usize) {
+        self.cycles.window = generations.max(1);
+        self.cycles.trim();
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+
+use wasm_bindgen::prelude::*;
+
+use crate::life_counter_combined::{Cell, Universe};
+
+const DEFAULT_HISTORY_LIMIT: usize = 10_000;
+
+/// The smallest rectangle containing every live cell.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Live-cell count and bounding box, kept up to date as cells change.
+///
+/// Births only ever grow the bounding box, so it is extended in place. A
+/// death on its edge may shrink it, which marks it stale until the next
+/// `bounding_box` call rescans the board.
+pub(crate) struct Population {
+    pub(crate) count: u32,
+    // (min_x, min_y, max_x, max_y), inclusive.
+    bounds: Option<(u32, u32, u32, u32)>,
+    bounds_stale: bool,
+    history: VecDeque<u32>,
+    history_start: u32,
+    history_limit: usize,
+}
+
+impl Population {
+    pub(crate) fn new() -> Population {
+        Population {
+            count: 0,
+            bounds: None,
+            bounds_stale: false,
+            history: VecDeque::new(),
+            history_start: 1,
+            history_limit: DEFAULT_HISTORY_LIMIT,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn cell_changed(&mut self, x: u32, y: u32, cell: Cell) {
+        if cell == Cell::Alive {
+            self.count += 1;
+            if !self.bounds_stale {
+                self.bounds = Some(match self.bounds {
+                    Some((min_x, min_y, max_x, max_y)) => {
+                        (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+                    }
+                    None => (x, y, x, y),
+                });
+            }
+        } else {
+            self.count -= 1;
+            if let Some((min_x, min_y, max_x, max_y)) = self.bounds {
+                if x == min_x || x == max_x || y == min_y || y == max_y {
+                    self.bounds_stale = true;
+                }
+            }
+        }
+    }
+
+    /// Appends the population reached by `generation` to the history.
+    pub(crate) fn record(&mut self, generation: u32) {
+        if self.history_start + self.history.len() as u32 != generation {
+            self.history.clear();
+            self.history_start = generation;
+        }
+        self.history.push_back(self.count);
+        while self.history.len() > self.history_limit {
+            self.history.pop_front();
+            self.history_start += 1;
+        }
+    }
+
+    /// Drops history entries after `generation`, for when the universe has
+    /// been moved back in time.
+    fn truncate_history(&mut self, generation: u32) {
+        let keep = generation.saturating_sub(self.history_start) as usize;
+        if generation < self.history_start {
+            self.history.clear();
+        } else {
+            self.history.truncate(keep + 1);
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    pub fn population(&self) -> u32 {
+        self.population.count
+    }
+
+    /// The bounding box of the live cells, or `undefined` for an empty board.
+    /// This ignores wrapping, so objects straddling an edge span the board.
+    pub fn bounding_box(&mut self) -> Option<BoundingBox> {
+        if self.population.bounds_stale {
+            self.population.bounds = self.scan_bounds();
+            self.population.bounds_stale = false;
+        }
+
+        self.population
+            .bounds
+            .map(|(min_x, min_y, max_x, max_y)| BoundingBox {
+                x: min_x,
+                y: min_y,
+                width: max_x - min_x + 1,
+                height: max_y - min_y + 1,
+            })
+    }
+
+    /// The population after each of the last generations, oldest first.
+    pub fn population_history(&self) -> Vec<u32> {
+        self.population.history.iter().copied().collect()
+    }
+
+    /// The generation of the first entry in `population_history`.
+    pub fn population_history_start(&self) -> u32 {
+        self.population.history_start
+    }
+
+    pub fn set_population_history_limit(&mut self, limit: usize) {
+        self.population.history_limit = limit;
+        while self.population.history.len() > limit {
+            self.population.history.pop_front();
+            self.population.history_start += 1;
+        }
+    }
+}
+
+impl Universe {
+    /// Recounts the population from scratch after `cells` has been replaced.
+    pub(crate) fn recount_population(&mut self) {
+        self.population.count = self
+            .cells
+            .iter()
+            .filter(|&&cell| cell == Cell::Alive)
+            .count() as u32;
+        self.population.bounds_stale = true;
+        self.population.truncate_history(self.tick_count);
+    }
+
+    fn scan_bounds(&self) -> Option<(u32, u32, u32, u32)> {
+        let mut bounds: Option<(u32, u32, u32, u32)> = None;
+        for y in 0..self.height {
+            let row = &self.cells[self.index(0, y)..self.index(0, y) + self.width as usize];
+            let first = match row.iter().position(|&cell| cell == Cell::Alive) {
+                Some(first) => first as u32,
+                None => continue,
+            };
+            let last = row.iter().rposition(|&cell| cell == Cell::Alive).unwrap() as u32;
+
+            bounds = Some(match bounds {
+                Some((min_x, min_y, max_x, _)) => (min_x.min(first), min_y, max_x.max(last), y),
+                None => (first, y, last, y),
+            });
+        }
+        bounds
+    }
+}
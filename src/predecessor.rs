@@ -0,0 +1,346 @@
+use wasm_bindgen::prelude::*;
+
+use crate::life_counter_combined::{Cell, Universe};
+use crate::motion::normalise;
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+use crate::sat::{Lit, Solver};
+
+/// A cell in a CNF encoding: either fixed or decided by the solver.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Term {
+    Fixed(bool),
+    Free(Lit),
+}
+
+/// Adds clauses requiring `next` to be what `rule` makes of `cell` and its
+/// eight `neighbours`.
+///
+/// The free neighbours are counted in unary by a totalizer, then each
+/// combination of the cell's state and the exact count rules out the wrong
+/// next state with one clause.
+pub(crate) fn encode_transition(
+    solver: &mut Solver,
+    rule: Rule,
+    cell: Term,
+    neighbours: &[Term; 8],
+    next: Term,
+) {
+    let mut fixed_alive = 0;
+    let mut free = Vec::with_capacity(8);
+    for term in neighbours {
+        match term {
+            Term::Fixed(true) => fixed_alive += 1,
+            Term::Fixed(false) => {}
+            Term::Free(lit) => free.push(*lit),
+        }
+    }
+    // `at_least[k - 1]` holds when at least `k` free neighbours are alive.
+    let at_least = totalize(solver, &free);
+
+    let states: &[bool] = match cell {
+        Term::Fixed(value) if value => &[true],
+        Term::Fixed(_) => &[false],
+        Term::Free(_) => &[false, true],
+    };
+    let mut clause = Vec::with_capacity(4);
+    for &state in states {
+        let cell_state = if state { Cell::Alive } else { Cell::Dead };
+        for count in 0..=free.len() {
+            let alive = rule.next(cell_state, fixed_alive + count as u8) == Cell::Alive;
+
+            clause.clear();
+            match next {
+                Term::Fixed(value) if value == alive => continue,
+                Term::Fixed(_) => {}
+                Term::Free(lit) => clause.push(if alive { lit } else { !lit }),
+            }
+            if let Term::Free(lit) = cell {
+                clause.push(if state { !lit } else { lit });
+            }
+            if count > 0 {
+                clause.push(!at_least[count - 1]);
+            }
+            if count < free.len() {
+                clause.push(at_least[count]);
+            }
+            solver.add_clause(&clause);
+        }
+    }
+}
+
+/// Returns literals `r` with `r[k - 1]` true exactly when at least `k` of
+/// `lits` are.
+fn totalize(solver: &mut Solver, lits: &[Lit]) -> Vec<Lit> {
+    if lits.len() <= 1 {
+        return lits.to_vec();
+    }
+    let (left, right) = lits.split_at(lits.len() / 2);
+    let a = totalize(solver, left);
+    let b = totalize(solver, right);
+    let r: Vec<Lit> = (0..a.len() + b.len())
+        .map(|_| Lit::new(solver.new_var(), true))
+        .collect();
+
+    for i in 0..=a.len() {
+        for j in 0..=b.len() {
+            // At least i in `a` and j in `b` makes at least i + j.
+            if i + j > 0 {
+                let mut clause = vec![r[i + j - 1]];
+                clause.extend(i.checked_sub(1).map(|i| !a[i]));
+                clause.extend(j.checked_sub(1).map(|j| !b[j]));
+                solver.add_clause(&clause);
+            }
+            // Fewer than i + 1 and j + 1 makes fewer than i + j + 1.
+            if i + j < r.len() {
+                let mut clause = vec![!r[i + j]];
+                clause.extend(a.get(i).copied());
+                clause.extend(b.get(j).copied());
+                solver.add_clause(&clause);
+            }
+        }
+    }
+    r
+}
+
+/// Columns (or rows) `start - margin ..= start + span - 1 + margin`, wrapped
+/// onto a ring of `n`, or the whole ring if that would overlap itself.
+fn band(start: u32, span: u32, margin: u32, n: u32) -> Vec<u32> {
+    if span as u64 + 2 * margin as u64 >= n as u64 {
+        return (0..n).collect();
+    }
+    let first = start + n - margin;
+    (0..span + 2 * margin).map(|i| (first + i) % n).collect()
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// Searches for a board which becomes this one after a single tick, with
+    /// its live cells no more than `margin` cells outside the bounding box of
+    /// this board's. Returns it as RLE cropped to its live cells, or
+    /// `undefined` if no such predecessor exists or if `max_conflicts` is
+    /// given and the solver gives up first.
+    pub fn find_predecessor(&self, margin: u32, max_conflicts: Option<u32>) -> Option<String> {
+        let cells = self.predecessor(margin, max_conflicts)??;
+        let pattern = match normalise(&cells, self.width, self.height) {
+            Some(normalised) => {
+                let mut pattern = Pattern::empty(normalised.width, normalised.height);
+                for &(x, y) in &normalised.cells {
+                    pattern.set(x, y, Cell::Alive);
+                }
+                pattern
+            }
+            None => Pattern::empty(0, 0),
+        };
+        Some(pattern.to_rle(self.rule))
+    }
+
+    /// Whether no predecessor exists within `margin` cells of the live cells'
+    /// bounding box, or `undefined` if `max_conflicts` is given and the
+    /// solver gives up before deciding. A `true` answer for a margin of 1 or
+    /// more rules out every predecessor confined to that region, though not
+    /// ones larger.
+    pub fn is_garden_of_eden(&self, margin: u32, max_conflicts: Option<u32>) -> Option<bool> {
+        self.predecessor(margin, max_conflicts)
+            .map(|cells| cells.is_none())
+    }
+
+    /// Replaces the board with a predecessor found as by `find_predecessor`,
+    /// going back a generation. Returns false if there is none, or
+    /// `undefined` if the solver gives up, leaving the board alone either
+    /// way.
+    pub fn reverse_step(&mut self, margin: u32, max_conflicts: Option<u32>) -> Option<bool> {
+        let cells = match self.predecessor(margin, max_conflicts)? {
+            Some(cells) => cells,
+            None => return Some(false),
+        };
+
+        self.edit_history.clear();
+        self.cells = cells;
+        self.tick_count = self.tick_count.saturating_sub(1);
+        if let Some(timeline) = &mut self.timeline {
+            timeline.reset(self.tick_count, &self.cells);
+        }
+        self.recalculate();
+        Some(true)
+    }
+}
+
+impl Universe {
+    /// Finds a full board which ticks to the current one, with live cells
+    /// only within `margin` of the current live cells. Returns `Some(None)`
+    /// if there is none, and `None` if the solver gives up after
+    /// `max_conflicts` conflicts.
+    pub(crate) fn predecessor(
+        &self,
+        margin: u32,
+        max_conflicts: Option<u32>,
+    ) -> Option<Option<Vec<Cell>>> {
+        let (width, height) = (self.width, self.height);
+        let bounds = match normalise(&self.cells, width, height) {
+            Some(bounds) => bounds,
+            // With no room for live cells, the only candidate is an empty
+            // board, which stays empty unless the rule has B0.
+            None if self.rule.next(Cell::Dead, 0) == Cell::Dead => {
+                return Some(Some(vec![Cell::Dead; self.cells.len()]))
+            }
+            None => return Some(None),
+        };
+
+        let mut solver = Solver::new();
+        let mut terms = vec![Term::Fixed(false); self.cells.len()];
+        let columns = band(bounds.x, bounds.width, margin, width);
+        let rows = band(bounds.y, bounds.height, margin, height);
+        for &y in &rows {
+            for &x in &columns {
+                terms[self.index(x, y)] = Term::Free(Lit::new(solver.new_var(), true));
+            }
+        }
+
+        let mut constrained = vec![false; self.cells.len()];
+        for &y in &band(bounds.y, bounds.height, margin + 1, height) {
+            for &x in &band(bounds.x, bounds.width, margin + 1, width) {
+                let idx = self.index(x, y);
+                constrained[idx] = true;
+                let mut neighbours = [Term::Fixed(false); 8];
+                for (neighbour, &(dx, dy)) in neighbours.iter_mut().zip(self.points.iter()) {
+                    *neighbour = terms[self.index((x + dx) % width, (y + dy) % height)];
+                }
+                let next = Term::Fixed(self.cells[idx] == Cell::Alive);
+                encode_transition(&mut solver, self.rule, terms[idx], &neighbours, next);
+            }
+        }
+
+        // Every other cell has only dead cells around it.
+        let background = self.rule.next(Cell::Dead, 0);
+        if (0..self.cells.len()).any(|idx| !constrained[idx] && self.cells[idx] != background) {
+            return Some(None);
+        }
+
+        if !solver.solve_limited(max_conflicts.map(u64::from))? {
+            return Some(None);
+        }
+        Some(Some(
+            terms
+                .iter()
+                .map(|term| match term {
+                    Term::Free(lit) if solver.model_value(*lit) => Cell::Alive,
+                    _ => Cell::Dead,
+                })
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use super::*;
+
+    fn board(width: u32, height: u32, live: &[(u32, u32)]) -> Universe {
+        let mut universe = Universe::empty(width, height, 0);
+        for &(x, y) in live {
+            let idx = universe.index(x, y);
+            universe.cells[idx] = Cell::Alive;
+        }
+        universe.recalculate();
+        universe
+    }
+
+    /// Checks `universe` has a predecessor within `margin` which ticks to it.
+    fn check_predecessor(universe: &Universe, margin: u32) {
+        let cells = universe.predecessor(margin, None).unwrap().unwrap();
+        let mut earlier = Universe::empty(universe.width, universe.height, 0);
+        earlier.rule = universe.rule;
+        earlier.cells = cells;
+        earlier.recalculate();
+        earlier.tick();
+        assert_eq!(earlier.cells, universe.cells);
+    }
+
+    #[test]
+    fn transition_encoding() {
+        // Every state of a cell and its neighbours, under two rules.
+        for rule in [Rule::CONWAY, "B36/S23".parse().unwrap()] {
+            for inputs in 0u32..1 << 9 {
+                let mut solver = Solver::new();
+                let lits: Vec<Lit> = (0..10).map(|_| Lit::new(solver.new_var(), true)).collect();
+                for (i, &lit) in lits[..9].iter().enumerate() {
+                    solver.add_clause(&[if inputs >> i & 1 == 1 { lit } else { !lit }]);
+                }
+                let terms: Vec<Term> = lits.iter().map(|&lit| Term::Free(lit)).collect();
+                let neighbours: [Term; 8] = terms[1..9].try_into().unwrap();
+                encode_transition(&mut solver, rule, terms[0], &neighbours, terms[9]);
+
+                let state = if inputs & 1 == 1 {
+                    Cell::Alive
+                } else {
+                    Cell::Dead
+                };
+                let alive = rule.next(state, (inputs >> 1).count_ones() as u8) == Cell::Alive;
+                assert!(solver.solve());
+                assert_eq!(solver.model_value(lits[9]), alive, "{:09b}", inputs);
+                solver.add_clause(&[if alive { !lits[9] } else { lits[9] }]);
+                assert!(!solver.solve(), "{:09b}", inputs);
+            }
+        }
+    }
+
+    #[test]
+    fn blinker_and_block() {
+        check_predecessor(&board(16, 16, &[(6, 7), (7, 7), (8, 7)]), 1);
+        check_predecessor(&board(16, 16, &[(7, 7), (8, 7), (7, 8), (8, 8)]), 1);
+        check_predecessor(&board(16, 16, &[(7, 7), (8, 7), (7, 8), (8, 8)]), 0);
+    }
+
+    #[test]
+    fn empty_board() {
+        let universe = board(8, 8, &[]);
+        assert_eq!(
+            universe.predecessor(1, None),
+            Some(Some(universe.cells.clone()))
+        );
+        assert_eq!(universe.is_garden_of_eden(1, None), Some(false));
+    }
+
+    #[test]
+    fn reverse_step() {
+        let mut universe = board(16, 16, &[(6, 7), (7, 7), (8, 7)]);
+        universe.tick();
+        let current = universe.cells.clone();
+
+        assert_eq!(universe.reverse_step(1, None), Some(true));
+        assert_eq!(universe.tick_count, 0);
+        universe.tick();
+        assert_eq!(universe.cells, current);
+    }
+
+    #[test]
+    fn reverse_step_failure() {
+        // A lone cell has too few neighbours in its own box to be born.
+        let mut universe = board(16, 16, &[(7, 7)]);
+        universe.tick_count = 5;
+        assert_eq!(universe.is_garden_of_eden(0, None), Some(true));
+        assert_eq!(universe.find_predecessor(0, None), None);
+
+        assert_eq!(universe.reverse_step(0, None), Some(false));
+        assert_eq!(universe.tick_count, 5);
+        assert_eq!(universe.cells, board(16, 16, &[(7, 7)]).cells);
+    }
+
+    #[test]
+    fn giving_up() {
+        let glider = [(7, 6), (8, 7), (6, 8), (7, 8), (8, 8)];
+        let mut universe = board(16, 16, &glider);
+        universe.tick_count = 5;
+        assert_eq!(universe.predecessor(1, Some(1)), None);
+        assert_eq!(universe.is_garden_of_eden(1, Some(1)), None);
+        assert_eq!(universe.find_predecessor(1, Some(1)), None);
+        assert_eq!(universe.reverse_step(1, Some(1)), None);
+        assert_eq!(universe.tick_count, 5);
+        assert_eq!(universe.cells, board(16, 16, &glider).cells);
+
+        assert_eq!(universe.is_garden_of_eden(1, None), Some(false));
+    }
+}
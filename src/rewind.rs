@@ -0,0 +1,305 @@
+use std::collections::VecDeque;
+
+use wasm_bindgen::prelude::*;
+
+use crate::life_counter_combined::{Cell, Universe};
+use crate::snapshot::{pack_cells, unpack_cells};
+
+struct Frame {
+    generation: u32,
+    // Full bit-packed state, present every `keyframe_interval` generations
+    // and always on the oldest frame.
+    keyframe: Option<Vec<u8>>,
+    // Indices of the cells which changed since the previous frame.
+    diff: Vec<u32>,
+}
+
+/// A bounded ring buffer of past generations, stored as keyframes plus XOR
+/// diffs between consecutive generations.
+pub(crate) struct Timeline {
+    frames: VecDeque<Frame>,
+    capacity: usize,
+    keyframe_interval: u32,
+    // Packed state of the generation the universe is currently showing.
+    current: Vec<u8>,
+}
+
+impl Timeline {
+    fn new(capacity: usize, keyframe_interval: u32, generation: u32, cells: &[Cell]) -> Timeline {
+        let mut timeline = Timeline {
+            frames: VecDeque::new(),
+            capacity: capacity.max(1),
+            keyframe_interval: keyframe_interval.max(1),
+            current: Vec::new(),
+        };
+        timeline.reset(generation, cells);
+        timeline
+    }
+
+    /// Drops every frame and starts again from `cells`.
+    pub(crate) fn reset(&mut self, generation: u32, cells: &[Cell]) {
+        self.current = pack_cells(cells);
+        self.frames.clear();
+        self.frames.push_back(Frame {
+            generation,
+            keyframe: Some(self.current.clone()),
+            diff: Vec::new(),
+        });
+    }
+
+    fn first(&self) -> u32 {
+        self.frames.front().unwrap().generation
+    }
+
+    fn last(&self) -> u32 {
+        self.frames.back().unwrap().generation
+    }
+
+    /// Records `cells` as `generation`, discarding any frames after the
+    /// previous generation left behind by seeking backwards.
+    pub(crate) fn record(&mut self, generation: u32, cells: &[Cell]) {
+        let previous = generation.wrapping_sub(1);
+        if generation == 0 || previous < self.first() || previous > self.last() {
+            self.reset(generation, cells);
+            return;
+        }
+        while self.last() > previous {
+            self.frames.pop_back();
+        }
+
+        let packed = pack_cells(cells);
+        let mut diff = Vec::new();
+        for (i, (a, b)) in self.current.iter().zip(packed.iter()).enumerate() {
+            let mut changed = a ^ b;
+            while changed != 0 {
+                let bit = changed.trailing_zeros();
+                diff.push(i as u32 * 8 + bit);
+                changed &= changed - 1;
+            }
+        }
+
+        let keyframe = if generation.is_multiple_of(self.keyframe_interval) {
+            Some(packed.clone())
+        } else {
+            None
+        };
+        self.current = packed;
+        self.frames.push_back(Frame {
+            generation,
+            keyframe,
+            diff,
+        });
+
+        self.evict();
+    }
+
+    /// Flips cell `idx` in the recorded `generation` after an edit, dropping
+    /// the generations which followed it as they no longer lead on from it.
+    pub(crate) fn cell_changed(&mut self, generation: u32, idx: usize) {
+        if generation < self.first() || generation > self.last() {
+            return;
+        }
+        while self.last() > generation {
+            self.frames.pop_back();
+        }
+
+        self.current[idx / 8] ^= 1 << (idx % 8);
+        let frame = self.frames.back_mut().unwrap();
+        if let Some(keyframe) = &mut frame.keyframe {
+            keyframe[idx / 8] ^= 1 << (idx % 8);
+        }
+        match frame.diff.binary_search(&(idx as u32)) {
+            Ok(i) => {
+                frame.diff.remove(i);
+            }
+            Err(i) => frame.diff.insert(i, idx as u32),
+        }
+    }
+
+    fn evict(&mut self) {
+        while self.frames.len() > self.capacity {
+            // The oldest frame is always a keyframe, so the new oldest frame
+            // can be made into one by applying its diff.
+            let mut state = self.frames.pop_front().unwrap().keyframe.unwrap();
+            let front = self.frames.front_mut().unwrap();
+            if front.keyframe.is_none() {
+                apply_diff(&mut state, &front.diff);
+                front.keyframe = Some(state);
+            }
+        }
+    }
+
+    /// Rebuilds the packed state of `generation` from the nearest keyframe.
+    fn state_at(&self, generation: u32) -> Option<Vec<u8>> {
+        if generation < self.first() || generation > self.last() {
+            return None;
+        }
+        let target = (generation - self.first()) as usize;
+        let start = (0..=target)
+            .rev()
+            .find(|&i| self.frames[i].keyframe.is_some())
+            .unwrap();
+
+        let mut state = self.frames[start].keyframe.clone().unwrap();
+        for frame in self.frames.range(start + 1..=target) {
+            apply_diff(&mut state, &frame.diff);
+        }
+        Some(state)
+    }
+}
+
+fn apply_diff(state: &mut [u8], diff: &[u32]) {
+    for &idx in diff {
+        state[idx as usize / 8] ^= 1 << (idx % 8);
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// Starts recording generations so they can be revisited with `seek`.
+    /// At most `capacity` generations are kept, with a full keyframe every
+    /// `keyframe_interval` generations and XOR diffs in between.
+    pub fn enable_history(&mut self, capacity: usize, keyframe_interval: u32) {
+        self.timeline = Some(Timeline::new(
+            capacity,
+            keyframe_interval,
+            self.tick_count,
+            &self.cells,
+        ));
+    }
+
+    pub fn disable_history(&mut self) {
+        self.timeline = None;
+    }
+
+    /// The oldest generation which can still be seeked to.
+    pub fn history_start(&self) -> Option<u32> {
+        self.timeline.as_ref().map(|timeline| timeline.first())
+    }
+
+    /// The newest recorded generation.
+    pub fn history_end(&self) -> Option<u32> {
+        self.timeline.as_ref().map(|timeline| timeline.last())
+    }
+
+    /// Moves to a recorded generation, returning `false` if it is no longer
+    /// (or not yet) in the history. Ticking or editing after seeking
+    /// backwards discards the generations which followed.
+    pub fn seek(&mut self, generation: u32) -> bool {
+        let state = match &self.timeline {
+            Some(timeline) => timeline.state_at(generation),
+            None => None,
+        };
+        let state = match state {
+            Some(state) => state,
+            None => return false,
+        };
+
+        self.edit_history.clear();
+        self.cells = unpack_cells(&state, self.cells.len());
+        self.tick_count = generation;
+        self.timeline.as_mut().unwrap().current = state;
+        self.recalculate();
+        true
+    }
+
+    pub fn step_back(&mut self) -> bool {
+        self.tick_count > 0 && self.seek(self.tick_count - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(universe: &mut Universe, ticks: u32) -> Vec<Vec<Cell>> {
+        let mut generations = vec![universe.cells.clone()];
+        for _ in 0..ticks {
+            universe.tick();
+            generations.push(universe.cells.clone());
+        }
+        generations
+    }
+
+    #[test]
+    fn seek_across_keyframes() {
+        let mut universe = Universe::new(32, 32, 0);
+        universe.enable_history(64, 4);
+        let generations = run(&mut universe, 10);
+
+        for generation in [9, 2, 5, 8, 0, 4, 3, 10, 1] {
+            assert!(universe.seek(generation));
+            assert_eq!(universe.tick_count, generation);
+            assert_eq!(universe.cells, generations[generation as usize]);
+        }
+    }
+
+    #[test]
+    fn seek_outside_the_window() {
+        let mut universe = Universe::new(32, 32, 0);
+        universe.enable_history(5, 3);
+        let generations = run(&mut universe, 10);
+        assert_eq!(universe.history_start(), Some(6));
+        assert_eq!(universe.history_end(), Some(10));
+
+        assert!(!universe.seek(5));
+        assert!(!universe.seek(11));
+        assert_eq!(universe.tick_count, 10);
+        assert_eq!(universe.cells, generations[10]);
+
+        // The oldest frames were rebuilt as keyframes as older ones went.
+        for generation in 6..=10 {
+            assert!(universe.seek(generation));
+            assert_eq!(universe.cells, generations[generation as usize]);
+        }
+        assert!(universe.seek(6));
+        assert!(!universe.step_back());
+    }
+
+    #[test]
+    fn ticking_after_seeking_back() {
+        let mut universe = Universe::new(32, 32, 0);
+        universe.enable_history(64, 4);
+        let generations = run(&mut universe, 10);
+
+        assert!(universe.seek(3));
+        universe.tick();
+        assert_eq!(universe.history_end(), Some(4));
+        assert_eq!(universe.cells, generations[4]);
+        assert!(universe.step_back());
+        assert_eq!(universe.cells, generations[3]);
+    }
+
+    #[test]
+    fn edits_then_ticks() {
+        let mut universe = Universe::new(32, 32, 0);
+        universe.enable_history(64, 4);
+        run(&mut universe, 3);
+        for x in 0..32 {
+            universe.set(x, 16, Cell::Alive);
+        }
+        let edited = universe.cells.clone();
+        let generations = run(&mut universe, 6);
+
+        assert!(universe.seek(3));
+        assert_eq!(universe.cells, edited);
+        for generation in 4..=9 {
+            assert!(universe.seek(generation));
+            assert_eq!(universe.cells, generations[generation as usize - 3]);
+        }
+
+        // Editing a past generation drops the ones after it.
+        assert!(universe.seek(5));
+        universe.set(0, 0, Cell::Alive);
+        universe.set(0, 0, Cell::Dead);
+        universe.set(1, 0, Cell::Alive);
+        let edited = universe.cells.clone();
+        assert_eq!(universe.history_end(), Some(5));
+        universe.tick();
+        let next = universe.cells.clone();
+        assert!(universe.step_back());
+        assert_eq!(universe.cells, edited);
+        assert!(universe.seek(6));
+        assert_eq!(universe.cells, next);
+    }
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::life_counter_combined::Cell;
+
+/// A Life-like birth / survival rule. Bit `n` of each mask is set when a cell
+/// with `n` live neighbours is born (or survives) in the next generation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rule {
+    birth: u16,
+    survival: u16,
+}
+
+impl Rule {
+    /// Conway's Game of Life, B3/S23.
+    pub const CONWAY: Rule = Rule {
+        birth: 1 << 3,
+        survival: 1 << 2 | 1 << 3,
+    };
+
+    const MASK: u16 = 0x1FF;
+
+    pub fn new(birth: u16, survival: u16) -> Rule {
+        Rule {
+            birth: birth & Self::MASK,
+            survival: survival & Self::MASK,
+        }
+    }
+
+    pub fn birth(&self) -> u16 {
+        self.birth
+    }
+
+    pub fn survival(&self) -> u16 {
+        self.survival
+    }
+
+    #[inline]
+    pub fn next(&self, cell: Cell, live_neighbours: u8) -> Cell {
+        let mask = match cell {
+            Cell::Alive => self.survival,
+            Cell::Dead => self.birth,
+        };
+        if (mask >> live_neighbours) & 1 == 1 {
+            Cell::Alive
+        } else {
+            Cell::Dead
+        }
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Rule {
+        Rule::CONWAY
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "B")?;
+        for n in 0..9 {
+            if (self.birth >> n) & 1 == 1 {
+                write!(f, "{}", n)?;
+            }
+        }
+        write!(f, "/S")?;
+        for n in 0..9 {
+            if (self.survival >> n) & 1 == 1 {
+                write!(f, "{}", n)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses rules written in `B3/S23` notation (case insensitive).
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Rule, String> {
+        let invalid = || format!("invalid rule '{}', expected B/S notation like B3/S23", s);
+
+        let upper = s.trim().to_ascii_uppercase();
+        let (birth, survival) = upper.split_once('/').ok_or_else(invalid)?;
+        let birth = birth.strip_prefix('B').ok_or_else(invalid)?;
+        let survival = survival.strip_prefix('S').ok_or_else(invalid)?;
+
+        let mask = |digits: &str| -> Result<u16, String> {
+            digits
+                .chars()
+                .try_fold(0u16, |mask, c| match c.to_digit(10) {
+                    Some(n) if n < 9 => Ok(mask | 1 << n),
+                    _ => Err(invalid()),
+                })
+        };
+
+        Ok(Rule::new(mask(birth)?, mask(survival)?))
+    }
+}
@@ -0,0 +1,109 @@
+use wasm_bindgen::prelude::*;
+
+use crate::life_counter_combined::Universe;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Condition {
+    PopulationEquals(u32),
+    PopulationBelow(u32),
+    PopulationAbove(u32),
+    Empty,
+    Static,
+    Periodic(u32),
+    CellChanges(u32, u32),
+}
+
+/// A condition which stops `Universe::run_until`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StopCondition {
+    condition: Condition,
+}
+
+#[wasm_bindgen]
+impl StopCondition {
+    pub fn population_equals(population: u32) -> StopCondition {
+        Condition::PopulationEquals(population).into()
+    }
+
+    pub fn population_below(population: u32) -> StopCondition {
+        Condition::PopulationBelow(population).into()
+    }
+
+    pub fn population_above(population: u32) -> StopCondition {
+        Condition::PopulationAbove(population).into()
+    }
+
+    pub fn empty() -> StopCondition {
+        Condition::Empty.into()
+    }
+
+    /// The board is identical to the previous generation.
+    pub fn static_board() -> StopCondition {
+        Condition::Static.into()
+    }
+
+    /// The board repeats a generation from at most `max_period` generations
+    /// ago. Static boards count as period 1. Periods longer than the cycle
+    /// window set with `set_cycle_window` are not detected.
+    pub fn periodic(max_period: u32) -> StopCondition {
+        Condition::Periodic(max_period.max(1)).into()
+    }
+
+    /// The given cell differs from its state when the run started.
+    pub fn cell_changes(x: u32, y: u32) -> StopCondition {
+        Condition::CellChanges(x, y).into()
+    }
+}
+
+impl From<Condition> for StopCondition {
+    fn from(condition: Condition) -> StopCondition {
+        StopCondition { condition }
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// Advances `n` generations in a single call.
+    pub fn tick_n(&mut self, n: u32) {
+        for _ in 0..n {
+            self.tick();
+        }
+    }
+
+    /// Ticks until `condition` holds or `max_generations` have passed. The
+    /// condition is checked after every tick, and the generation at which it
+    /// held is returned.
+    pub fn run_until(&mut self, condition: &StopCondition, max_generations: u32) -> Option<u32> {
+        if let Condition::Periodic(_) = condition.condition {
+            self.cycles.record(self.tick_count);
+        }
+        let watched = match condition.condition {
+            Condition::CellChanges(x, y) => Some(self.get(x, y)),
+            _ => None,
+        };
+
+        for _ in 0..max_generations {
+            self.tick();
+
+            let fired = match condition.condition {
+                Condition::PopulationEquals(n) => self.population.count == n,
+                Condition::PopulationBelow(n) => self.population.count < n,
+                Condition::PopulationAbove(n) => self.population.count > n,
+                Condition::Empty => self.population.count == 0,
+                Condition::Static => self.cells == self.previous_cells,
+                Condition::Periodic(max_period) => self
+                    .cycles
+                    .cycle()
+                    .is_some_and(|cycle| cycle.period <= max_period),
+                Condition::CellChanges(x, y) => Some(self.get(x, y)) != watched,
+            };
+
+            if fired {
+                return Some(self.tick_count);
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,827 @@
+use std::ops::Not;
+
+/// A boolean variable of a `Solver`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Var(u32);
+
+impl Var {
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A variable or its negation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Lit(u32);
+
+impl Lit {
+    /// The literal which is true when `var` has `value`.
+    pub(crate) fn new(var: Var, value: bool) -> Lit {
+        Lit(var.0 << 1 | !value as u32)
+    }
+
+    pub(crate) fn var(self) -> Var {
+        Var(self.0 >> 1)
+    }
+
+    fn is_negative(self) -> bool {
+        self.0 & 1 == 1
+    }
+
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Not for Lit {
+    type Output = Lit;
+
+    fn not(self) -> Lit {
+        Lit(self.0 ^ 1)
+    }
+}
+
+struct Clause {
+    lits: Vec<Lit>,
+    learnt: bool,
+    deleted: bool,
+    activity: f64,
+    // Number of distinct decision levels among the literals when learnt.
+    lbd: u32,
+}
+
+/// A clause watching a literal, with another of its literals which, if
+/// true, means the clause need not be looked at.
+#[derive(Clone, Copy)]
+struct Watch {
+    clause: usize,
+    blocker: Lit,
+}
+
+const VAR_DECAY: f64 = 0.95;
+const CLAUSE_DECAY: f64 = 0.999;
+const RESTART_UNIT: u64 = 100;
+
+/// A conflict-driven clause-learning SAT solver.
+///
+/// Clauses are watched by two literals, decisions follow VSIDS activity with
+/// saved phases, learnt clauses are minimised and periodically halved, and
+/// the search restarts on the Luby sequence. Clauses may be added between
+/// calls to `solve`, which is how solutions are excluded one by one.
+pub(crate) struct Solver {
+    clauses: Vec<Clause>,
+    // Slots of deleted clauses, reused for new ones.
+    free_slots: Vec<usize>,
+    originals: usize,
+    // Clauses watching each literal, visited when it becomes false.
+    watches: Vec<Vec<Watch>>,
+    values: Vec<Option<bool>>,
+    levels: Vec<u32>,
+    reasons: Vec<Option<usize>>,
+    phases: Vec<bool>,
+    activity: Vec<f64>,
+    var_inc: f64,
+    clause_inc: f64,
+    order: VarHeap,
+    trail: Vec<Lit>,
+    trail_limits: Vec<usize>,
+    propagated: usize,
+    seen: Vec<bool>,
+    learnts: usize,
+    max_learnts: f64,
+    model: Vec<bool>,
+    // False once the clauses are known to be unsatisfiable.
+    ok: bool,
+}
+
+impl Solver {
+    pub(crate) fn new() -> Solver {
+        Solver {
+            clauses: Vec::new(),
+            free_slots: Vec::new(),
+            originals: 0,
+            watches: Vec::new(),
+            values: Vec::new(),
+            levels: Vec::new(),
+            reasons: Vec::new(),
+            phases: Vec::new(),
+            activity: Vec::new(),
+            var_inc: 1.0,
+            clause_inc: 1.0,
+            order: VarHeap::default(),
+            trail: Vec::new(),
+            trail_limits: Vec::new(),
+            propagated: 0,
+            seen: Vec::new(),
+            learnts: 0,
+            max_learnts: 0.0,
+            model: Vec::new(),
+            ok: true,
+        }
+    }
+
+    pub(crate) fn new_var(&mut self) -> Var {
+        let var = Var(self.values.len() as u32);
+        self.values.push(None);
+        self.levels.push(0);
+        self.reasons.push(None);
+        // Preferring false finds sparse patterns first.
+        self.phases.push(false);
+        self.activity.push(0.0);
+        self.seen.push(false);
+        self.watches.push(Vec::new());
+        self.watches.push(Vec::new());
+        self.order.insert(var, &self.activity);
+        var
+    }
+
+    /// Adds a clause, the disjunction of `lits`.
+    pub(crate) fn add_clause(&mut self, lits: &[Lit]) {
+        if !self.ok {
+            return;
+        }
+        self.cancel_until(0);
+
+        let mut clause: Vec<Lit> = Vec::with_capacity(lits.len());
+        for &lit in lits {
+            match self.value(lit) {
+                Some(true) => return,
+                Some(false) => continue,
+                None => {}
+            }
+            if clause.contains(&!lit) {
+                return;
+            }
+            if !clause.contains(&lit) {
+                clause.push(lit);
+            }
+        }
+
+        match clause.len() {
+            0 => self.ok = false,
+            1 => {
+                self.assign(clause[0], None);
+                self.ok = self.propagate().is_none();
+            }
+            _ => {
+                self.attach(clause, false, 0);
+                self.originals += 1;
+            }
+        }
+    }
+
+    /// Whether the clauses can all be satisfied. The satisfying assignment is
+    /// then available through `model_value`.
+    #[cfg(test)]
+    pub(crate) fn solve(&mut self) -> bool {
+        self.solve_limited(None).unwrap()
+    }
+
+    /// Like `solve`, giving up with `None` after `max_conflicts` conflicts.
+    pub(crate) fn solve_limited(&mut self, max_conflicts: Option<u64>) -> Option<bool> {
+        if !self.ok {
+            return Some(false);
+        }
+        self.cancel_until(0);
+        self.max_learnts = self.originals as f64 / 3.0 + 1000.0;
+
+        let mut conflicts = 0;
+        let mut restarts = 0;
+        let mut restart_limit = luby(restarts) * RESTART_UNIT;
+        let mut since_restart = 0;
+        // The learnt clause limit grows geometrically, in ever longer steps.
+        let mut adjust_interval = 100.0;
+        let mut next_adjust = adjust_interval as u64;
+
+        loop {
+            if let Some(conflict) = self.propagate() {
+                conflicts += 1;
+                since_restart += 1;
+                if self.decision_level() == 0 {
+                    self.ok = false;
+                    return Some(false);
+                }
+
+                let (learnt, level) = self.analyze(conflict);
+                let lbd = self.lbd(&learnt);
+                self.cancel_until(level);
+                let asserting = learnt[0];
+                if learnt.len() == 1 {
+                    self.assign(asserting, None);
+                } else {
+                    let clause = self.attach(learnt, true, lbd);
+                    self.bump_clause(clause);
+                    self.assign(asserting, Some(clause));
+                }
+                self.var_inc /= VAR_DECAY;
+                self.clause_inc /= CLAUSE_DECAY;
+
+                if max_conflicts.is_some_and(|max| conflicts >= max) {
+                    self.cancel_until(0);
+                    return None;
+                }
+                if since_restart >= restart_limit {
+                    self.cancel_until(0);
+                    restarts += 1;
+                    restart_limit = luby(restarts) * RESTART_UNIT;
+                    since_restart = 0;
+                }
+                if conflicts == next_adjust {
+                    adjust_interval *= 1.5;
+                    next_adjust += adjust_interval as u64;
+                    self.max_learnts *= 1.1;
+                }
+            } else {
+                if self.learnts as f64 >= self.max_learnts + self.trail.len() as f64 {
+                    self.reduce_learnts();
+                }
+
+                match self.pick_branch() {
+                    Some(lit) => {
+                        self.trail_limits.push(self.trail.len());
+                        self.assign(lit, None);
+                    }
+                    None => {
+                        self.model = self.values.iter().map(|v| v.unwrap()).collect();
+                        self.cancel_until(0);
+                        return Some(true);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The value of `lit` in the last satisfying assignment.
+    pub(crate) fn model_value(&self, lit: Lit) -> bool {
+        self.model[lit.var().index()] != lit.is_negative()
+    }
+
+    fn value(&self, lit: Lit) -> Option<bool> {
+        self.values[lit.var().index()].map(|value| value != lit.is_negative())
+    }
+
+    fn decision_level(&self) -> u32 {
+        self.trail_limits.len() as u32
+    }
+
+    fn attach(&mut self, lits: Vec<Lit>, learnt: bool, lbd: u32) -> usize {
+        let clause = Clause {
+            lits,
+            learnt,
+            deleted: false,
+            activity: 0.0,
+            lbd,
+        };
+        let idx = match self.free_slots.pop() {
+            Some(idx) => {
+                self.clauses[idx] = clause;
+                idx
+            }
+            None => {
+                self.clauses.push(clause);
+                self.clauses.len() - 1
+            }
+        };
+
+        let (a, b) = (self.clauses[idx].lits[0], self.clauses[idx].lits[1]);
+        self.watches[a.index()].push(Watch {
+            clause: idx,
+            blocker: b,
+        });
+        self.watches[b.index()].push(Watch {
+            clause: idx,
+            blocker: a,
+        });
+        if learnt {
+            self.learnts += 1;
+        }
+        idx
+    }
+
+    fn lbd(&self, lits: &[Lit]) -> u32 {
+        let mut levels: Vec<u32> = lits
+            .iter()
+            .map(|lit| self.levels[lit.var().index()])
+            .collect();
+        levels.sort_unstable();
+        levels.dedup();
+        levels.len() as u32
+    }
+
+    fn assign(&mut self, lit: Lit, reason: Option<usize>) {
+        let var = lit.var().index();
+        self.values[var] = Some(!lit.is_negative());
+        self.levels[var] = self.decision_level();
+        self.reasons[var] = reason;
+        self.trail.push(lit);
+    }
+
+    fn cancel_until(&mut self, level: u32) {
+        if self.decision_level() <= level {
+            return;
+        }
+        let limit = self.trail_limits[level as usize];
+        for lit in self.trail.drain(limit..).rev() {
+            let var = lit.var();
+            self.phases[var.index()] = !lit.is_negative();
+            self.values[var.index()] = None;
+            self.reasons[var.index()] = None;
+            self.order.insert(var, &self.activity);
+        }
+        self.trail_limits.truncate(level as usize);
+        self.propagated = self.trail.len();
+    }
+
+    /// Assigns every literal implied by unit clauses, returning the first
+    /// clause found to be false.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.propagated < self.trail.len() {
+            let false_lit = !self.trail[self.propagated];
+            self.propagated += 1;
+
+            let mut watching = std::mem::take(&mut self.watches[false_lit.index()]);
+            let mut kept = 0;
+            let mut conflict = None;
+            let mut i = 0;
+            while i < watching.len() {
+                let watch = watching[i];
+                i += 1;
+                if self.value(watch.blocker) == Some(true) {
+                    watching[kept] = watch;
+                    kept += 1;
+                    continue;
+                }
+
+                let lits = &mut self.clauses[watch.clause].lits;
+                if lits[0] == false_lit {
+                    lits.swap(0, 1);
+                }
+                let first = lits[0];
+                let kept_watch = Watch {
+                    clause: watch.clause,
+                    blocker: first,
+                };
+                if self.values[first.var().index()] == Some(!first.is_negative()) {
+                    watching[kept] = kept_watch;
+                    kept += 1;
+                    continue;
+                }
+
+                let values = &self.values;
+                let replacement = (2..lits.len()).find(|&k| {
+                    let lit = lits[k];
+                    values[lit.var().index()] != Some(lit.is_negative())
+                });
+                if let Some(k) = replacement {
+                    lits.swap(1, k);
+                    let watched = lits[1];
+                    self.watches[watched.index()].push(kept_watch);
+                    continue;
+                }
+
+                watching[kept] = kept_watch;
+                kept += 1;
+                if self.value(first) == Some(false) {
+                    conflict = Some(watch.clause);
+                    while i < watching.len() {
+                        watching[kept] = watching[i];
+                        kept += 1;
+                        i += 1;
+                    }
+                } else {
+                    self.assign(first, Some(watch.clause));
+                }
+            }
+
+            watching.truncate(kept);
+            self.watches[false_lit.index()] = watching;
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    /// Derives the first-UIP clause from `conflict`, returning it with the
+    /// asserting literal first and the level to backjump to.
+    fn analyze(&mut self, mut conflict: usize) -> (Vec<Lit>, u32) {
+        let mut learnt = vec![Lit(0)];
+        let mut pending = 0;
+        let mut implied: Option<Lit> = None;
+        let mut idx = self.trail.len();
+
+        loop {
+            self.bump_clause(conflict);
+            let skip = if implied.is_some() { 1 } else { 0 };
+            for k in skip..self.clauses[conflict].lits.len() {
+                let lit = self.clauses[conflict].lits[k];
+                let var = lit.var();
+                if self.seen[var.index()] || self.levels[var.index()] == 0 {
+                    continue;
+                }
+                self.seen[var.index()] = true;
+                self.bump_var(var);
+                if self.levels[var.index()] == self.decision_level() {
+                    pending += 1;
+                } else {
+                    learnt.push(lit);
+                }
+            }
+
+            loop {
+                idx -= 1;
+                if self.seen[self.trail[idx].var().index()] {
+                    break;
+                }
+            }
+            let lit = self.trail[idx];
+            self.seen[lit.var().index()] = false;
+            implied = Some(lit);
+            pending -= 1;
+            if pending == 0 {
+                break;
+            }
+            conflict = self.reasons[lit.var().index()].unwrap();
+        }
+        learnt[0] = !implied.unwrap();
+
+        // Drop literals implied by the rest of the clause.
+        let mut marked: Vec<Var> = learnt[1..].iter().map(|lit| lit.var()).collect();
+        let mut minimised = vec![learnt[0]];
+        for &lit in &learnt[1..] {
+            if self.reasons[lit.var().index()].is_none() || !self.redundant(lit, &mut marked) {
+                minimised.push(lit);
+            }
+        }
+        for var in marked {
+            self.seen[var.index()] = false;
+        }
+
+        let mut level = 0;
+        if minimised.len() > 1 {
+            let (max, _) = minimised
+                .iter()
+                .enumerate()
+                .skip(1)
+                .max_by_key(|(_, lit)| self.levels[lit.var().index()])
+                .unwrap();
+            minimised.swap(1, max);
+            level = self.levels[minimised[1].var().index()];
+        }
+        (minimised, level)
+    }
+
+    /// Whether `lit`, false and implied, is implied by literals already in
+    /// the learnt clause. Every variable shown to be is left marked in
+    /// `seen`, and added to `marked` for clearing later.
+    fn redundant(&mut self, lit: Lit, marked: &mut Vec<Var>) -> bool {
+        let mut stack = vec![lit];
+        let start = marked.len();
+        while let Some(lit) = stack.pop() {
+            let reason = self.reasons[lit.var().index()].unwrap();
+            for k in 1..self.clauses[reason].lits.len() {
+                let other = self.clauses[reason].lits[k];
+                let var = other.var();
+                if self.seen[var.index()] || self.levels[var.index()] == 0 {
+                    continue;
+                }
+                if self.reasons[var.index()].is_none() {
+                    // Reached a decision: undo the marks made on the way.
+                    for var in marked.drain(start..) {
+                        self.seen[var.index()] = false;
+                    }
+                    return false;
+                }
+                self.seen[var.index()] = true;
+                marked.push(var);
+                stack.push(other);
+            }
+        }
+        true
+    }
+
+    fn pick_branch(&mut self) -> Option<Lit> {
+        while let Some(var) = self.order.pop(&self.activity) {
+            if self.values[var.index()].is_none() {
+                return Some(Lit::new(var, self.phases[var.index()]));
+            }
+        }
+        None
+    }
+
+    fn bump_var(&mut self, var: Var) {
+        self.activity[var.index()] += self.var_inc;
+        if self.activity[var.index()] > 1e100 {
+            self.activity.iter_mut().for_each(|a| *a *= 1e-100);
+            self.var_inc *= 1e-100;
+        }
+        self.order.increased(var, &self.activity);
+    }
+
+    fn bump_clause(&mut self, idx: usize) {
+        let clause = &mut self.clauses[idx];
+        if !clause.learnt {
+            return;
+        }
+        clause.activity += self.clause_inc;
+        if clause.activity > 1e20 {
+            for clause in self.clauses.iter_mut().filter(|c| c.learnt) {
+                clause.activity *= 1e-20;
+            }
+            self.clause_inc *= 1e-20;
+        }
+    }
+
+    /// Deletes half of the learnt clauses, those with the most decision
+    /// levels and then the least active. Clauses spanning two levels are
+    /// kept, as are those which are the reason for a current assignment.
+    fn reduce_learnts(&mut self) {
+        let mut candidates: Vec<usize> = (0..self.clauses.len())
+            .filter(|&idx| {
+                let clause = &self.clauses[idx];
+                clause.learnt && !clause.deleted && clause.lbd > 2 && !self.is_reason(idx)
+            })
+            .collect();
+        candidates.sort_by(|&a, &b| {
+            let (a, b) = (&self.clauses[a], &self.clauses[b]);
+            b.lbd
+                .cmp(&a.lbd)
+                .then(a.activity.partial_cmp(&b.activity).unwrap())
+        });
+
+        for &idx in &candidates[..candidates.len() / 2] {
+            let clause = &mut self.clauses[idx];
+            clause.deleted = true;
+            clause.lits = Vec::new();
+            self.learnts -= 1;
+        }
+        let clauses = &self.clauses;
+        for watching in &mut self.watches {
+            watching.retain(|watch| !clauses[watch.clause].deleted);
+        }
+        self.free_slots
+            .extend(candidates[..candidates.len() / 2].iter().copied());
+    }
+
+    fn is_reason(&self, idx: usize) -> bool {
+        let first = self.clauses[idx].lits[0];
+        self.reasons[first.var().index()] == Some(idx) && self.value(first) == Some(true)
+    }
+}
+
+/// Unassigned variables ordered by activity, most active first.
+#[derive(Default)]
+struct VarHeap {
+    heap: Vec<Var>,
+    // Position of each variable in `heap`.
+    positions: Vec<Option<usize>>,
+}
+
+impl VarHeap {
+    fn insert(&mut self, var: Var, activity: &[f64]) {
+        if self.positions.len() <= var.index() {
+            self.positions.resize(var.index() + 1, None);
+        }
+        if self.positions[var.index()].is_some() {
+            return;
+        }
+        self.heap.push(var);
+        self.positions[var.index()] = Some(self.heap.len() - 1);
+        self.sift_up(self.heap.len() - 1, activity);
+    }
+
+    fn increased(&mut self, var: Var, activity: &[f64]) {
+        if let Some(pos) = self.positions[var.index()] {
+            self.sift_up(pos, activity);
+        }
+    }
+
+    fn pop(&mut self, activity: &[f64]) -> Option<Var> {
+        let top = *self.heap.first()?;
+        let last = self.heap.pop().unwrap();
+        self.positions[top.index()] = None;
+        if !self.heap.is_empty() {
+            self.heap[0] = last;
+            self.positions[last.index()] = Some(0);
+            self.sift_down(0, activity);
+        }
+        Some(top)
+    }
+
+    fn sift_up(&mut self, mut pos: usize, activity: &[f64]) {
+        while pos > 0 {
+            let parent = (pos - 1) / 2;
+            if activity[self.heap[parent].index()] >= activity[self.heap[pos].index()] {
+                break;
+            }
+            self.swap(pos, parent);
+            pos = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut pos: usize, activity: &[f64]) {
+        loop {
+            let left = 2 * pos + 1;
+            let right = left + 1;
+            let mut largest = pos;
+            for child in [left, right] {
+                if child < self.heap.len()
+                    && activity[self.heap[child].index()] > activity[self.heap[largest].index()]
+                {
+                    largest = child;
+                }
+            }
+            if largest == pos {
+                break;
+            }
+            self.swap(pos, largest);
+            pos = largest;
+        }
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.positions[self.heap[a].index()] = Some(a);
+        self.positions[self.heap[b].index()] = Some(b);
+    }
+}
+
+/// The `i`th term (from zero) of the Luby sequence 1, 1, 2, 1, 1, 2, 4, ...
+fn luby(mut i: u64) -> u64 {
+    let mut size = 1;
+    let mut power = 1;
+    while size < i + 1 {
+        size = 2 * size + 1;
+        power *= 2;
+    }
+    while size - 1 != i {
+        size = (size - 1) / 2;
+        power /= 2;
+        i %= size;
+    }
+    power
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::SplitMix64;
+
+    fn vars(solver: &mut Solver, n: usize) -> Vec<Var> {
+        (0..n).map(|_| solver.new_var()).collect()
+    }
+
+    fn satisfied(solver: &Solver, clauses: &[Vec<Lit>]) -> bool {
+        clauses
+            .iter()
+            .all(|clause| clause.iter().any(|&lit| solver.model_value(lit)))
+    }
+
+    #[test]
+    fn satisfiable() {
+        let mut solver = Solver::new();
+        let v = vars(&mut solver, 4);
+        let (a, b, c, d) = (
+            Lit::new(v[0], true),
+            Lit::new(v[1], true),
+            Lit::new(v[2], true),
+            Lit::new(v[3], true),
+        );
+        let clauses = vec![
+            vec![a, b],
+            vec![!a, c],
+            vec![!b, !c],
+            vec![!c, d],
+            vec![!d, !b],
+            vec![!b, a],
+        ];
+        for clause in &clauses {
+            solver.add_clause(clause);
+        }
+
+        assert!(solver.solve());
+        assert!(satisfied(&solver, &clauses));
+        // The only solution.
+        assert!(solver.model_value(a) && !solver.model_value(b));
+        assert!(solver.model_value(c) && solver.model_value(d));
+    }
+
+    /// Whether `pigeons` pigeons fit in `holes` holes, one to a hole.
+    fn pigeonhole(pigeons: usize, holes: usize) -> Solver {
+        let mut solver = Solver::new();
+        let v = vars(&mut solver, pigeons * holes);
+        let in_hole = |pigeon: usize, hole: usize| Lit::new(v[pigeon * holes + hole], true);
+        for pigeon in 0..pigeons {
+            let clause: Vec<Lit> = (0..holes).map(|hole| in_hole(pigeon, hole)).collect();
+            solver.add_clause(&clause);
+        }
+        for hole in 0..holes {
+            for p in 0..pigeons {
+                for q in p + 1..pigeons {
+                    solver.add_clause(&[!in_hole(p, hole), !in_hole(q, hole)]);
+                }
+            }
+        }
+        solver
+    }
+
+    #[test]
+    fn unsatisfiable() {
+        let mut solver = pigeonhole(4, 3);
+        assert!(!solver.solve());
+        // Stays unsatisfiable however it is asked.
+        assert_eq!(solver.solve_limited(Some(1)), Some(false));
+
+        assert!(pigeonhole(4, 4).solve());
+    }
+
+    #[test]
+    fn many_conflicts() {
+        // Hard enough to restart and throw learnt clauses away.
+        let mut solver = pigeonhole(8, 7);
+        assert_eq!(solver.solve_limited(Some(10)), None);
+        assert!(!solver.solve());
+    }
+
+    #[test]
+    fn contradictory_units() {
+        let mut solver = Solver::new();
+        let a = Lit::new(solver.new_var(), true);
+        solver.add_clause(&[a]);
+        assert!(solver.solve());
+        solver.add_clause(&[!a]);
+        assert!(!solver.solve());
+    }
+
+    #[test]
+    fn random_instances_match_brute_force() {
+        let mut rng = SplitMix64::new(36);
+        for _ in 0..200 {
+            let n = 4 + (rng.next_u64() % 9) as usize;
+            let m = n * 4 + (rng.next_u64() % (n as u64 * 2)) as usize;
+            let mut solver = Solver::new();
+            let v = vars(&mut solver, n);
+            let clauses: Vec<Vec<Lit>> = (0..m)
+                .map(|_| {
+                    (0..3)
+                        .map(|_| {
+                            let var = v[(rng.next_u64() % n as u64) as usize];
+                            Lit::new(var, rng.next_u64() & 1 == 1)
+                        })
+                        .collect()
+                })
+                .collect();
+            for clause in &clauses {
+                solver.add_clause(clause);
+            }
+
+            let brute = (0u32..1 << n).any(|assignment| {
+                clauses.iter().all(|clause| {
+                    clause.iter().any(|&lit| {
+                        let value = assignment >> lit.var().index() & 1 == 1;
+                        value != lit.is_negative()
+                    })
+                })
+            });
+            assert_eq!(solver.solve(), brute);
+            if brute {
+                assert!(satisfied(&solver, &clauses));
+            }
+        }
+    }
+
+    #[test]
+    fn enumerates_every_solution() {
+        // Exactly two of five, excluded one solution at a time.
+        let mut solver = Solver::new();
+        let v = vars(&mut solver, 5);
+        let lits: Vec<Lit> = v.iter().map(|&var| Lit::new(var, true)).collect();
+        for i in 0..5 {
+            // At least two: any four contain one.
+            let others: Vec<Lit> = (0..5).filter(|&j| j != i).map(|j| lits[j]).collect();
+            solver.add_clause(&others);
+            for j in i + 1..5 {
+                for k in j + 1..5 {
+                    solver.add_clause(&[!lits[i], !lits[j], !lits[k]]);
+                }
+            }
+        }
+
+        let mut solutions = 0;
+        while solver.solve() {
+            solutions += 1;
+            let blocking: Vec<Lit> = lits
+                .iter()
+                .map(|&lit| if solver.model_value(lit) { !lit } else { lit })
+                .collect();
+            solver.add_clause(&blocking);
+        }
+        assert_eq!(solutions, 10);
+    }
+
+    #[test]
+    fn luby_sequence() {
+        let terms: Vec<u64> = (0..15).map(luby).collect();
+        assert_eq!(terms, [1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]);
+    }
+}
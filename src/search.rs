@@ -0,0 +1,341 @@
+use wasm_bindgen::prelude::*;
+
+use crate::life_counter_combined::{Cell, Universe};
+use crate::pattern::Pattern;
+use crate::predecessor::{encode_transition, Term};
+use crate::rule::Rule;
+use crate::sat::{Lit, Solver};
+use crate::soup::Symmetry;
+
+/// A search for oscillators or spaceships which fit in a `width` by `height`
+/// box and repeat every `period` generations, moved by `(dx, dy)`.
+///
+/// Every generation of the pattern is a grid of solver variables, tied
+/// together by the rule's transition and required to return to the first
+/// generation, translated, after `period` ticks. Patterns which repeat
+/// sooner are excluded, as are ones already found in any phase or position.
+#[wasm_bindgen]
+pub struct PatternSearch {
+    width: u32,
+    height: u32,
+    period: u32,
+    dx: i32,
+    dy: i32,
+    rule: Rule,
+    symmetry: Symmetry,
+    // Built on the first call to `next`.
+    solver: Option<Solver>,
+    // Cells of each generation, row by row.
+    generations: Vec<Vec<Term>>,
+    found: u32,
+    exhausted: bool,
+}
+
+#[wasm_bindgen]
+impl PatternSearch {
+    pub fn new(
+        width: u32,
+        height: u32,
+        period: u32,
+        dx: i32,
+        dy: i32,
+    ) -> Result<PatternSearch, JsValue> {
+        if width == 0 || height == 0 || period == 0 {
+            return Err(JsValue::from_str(
+                "search box and period must be greater than zero",
+            ));
+        }
+        Ok(PatternSearch {
+            width,
+            height,
+            period,
+            dx,
+            dy,
+            rule: Rule::CONWAY,
+            symmetry: Symmetry::C1,
+            solver: None,
+            generations: Vec::new(),
+            found: 0,
+            exhausted: false,
+        })
+    }
+
+    /// Sets the rule to search under, restarting the search.
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), JsValue> {
+        self.rule = rule
+            .parse()
+            .map_err(|err: String| JsValue::from_str(&err))?;
+        self.restart();
+        Ok(())
+    }
+
+    /// Requires the first generation to have `symmetry` within the search
+    /// box, restarting the search. A spaceship's symmetry has to agree with
+    /// its direction of travel for any to be found.
+    pub fn set_symmetry(&mut self, symmetry: Symmetry) -> Result<(), JsValue> {
+        if !symmetry.fits(self.width, self.height) {
+            return Err(JsValue::from_str(
+                "rotational and diagonal symmetries need a square search box",
+            ));
+        }
+        self.symmetry = symmetry;
+        self.restart();
+        Ok(())
+    }
+
+    /// Looks for the next pattern, returning it as RLE. Returns `undefined`
+    /// once the search is exhausted, or if `max_conflicts` is given and the
+    /// solver gives up first, in which case calling again carries on.
+    pub fn next(&mut self, max_conflicts: Option<u32>) -> Option<String> {
+        if self.exhausted {
+            return None;
+        }
+        if self.solver.is_none() {
+            self.build();
+        }
+
+        let solver = self.solver.as_mut().unwrap();
+        match solver.solve_limited(max_conflicts.map(u64::from)) {
+            Some(true) => {}
+            Some(false) => {
+                self.exhausted = true;
+                return None;
+            }
+            None => return None,
+        }
+
+        let phases: Vec<Pattern> = (0..self.period as usize).map(|t| self.phase(t)).collect();
+        for phase in &phases {
+            self.exclude(phase);
+        }
+        self.found += 1;
+        Some(phases[0].to_rle(self.rule))
+    }
+
+    /// Whether every pattern has been found.
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+
+    /// How many patterns have been found so far.
+    pub fn found(&self) -> u32 {
+        self.found
+    }
+}
+
+impl PatternSearch {
+    fn restart(&mut self) {
+        self.solver = None;
+        self.generations.clear();
+        self.found = 0;
+        self.exhausted = false;
+    }
+
+    fn build(&mut self) {
+        let (width, height) = (self.width, self.height);
+        let mut solver = Solver::new();
+
+        // Cells sharing a representative under the symmetry share a variable.
+        let mut first = vec![Term::Fixed(false); (width * height) as usize];
+        for y in 0..height {
+            for x in 0..width {
+                let (rx, ry) = self.symmetry.representative(x, y, width, height);
+                first[(y * width + x) as usize] = if (rx, ry) == (x, y) {
+                    Term::Free(Lit::new(solver.new_var(), true))
+                } else {
+                    first[(ry * width + rx) as usize]
+                };
+            }
+        }
+        self.generations = vec![first];
+        for _ in 1..self.period {
+            let generation = (0..width * height)
+                .map(|_| Term::Free(Lit::new(solver.new_var(), true)))
+                .collect();
+            self.generations.push(generation);
+        }
+
+        // Each generation ticks into the next, with the last one ticking
+        // into a translated copy of the first. Other cells more than one
+        // outside the box have no live neighbours, so need no clauses.
+        let offsets: [(i32, i32); 8] = [
+            (-1, -1),
+            (0, -1),
+            (1, -1),
+            (-1, 0),
+            (1, 0),
+            (-1, 1),
+            (0, 1),
+            (1, 1),
+        ];
+        for t in 0..self.period {
+            let next_t = (t + 1) % self.period;
+            let (shift_x, shift_y) = if next_t == 0 {
+                (self.dx, self.dy)
+            } else {
+                (0, 0)
+            };
+            // Cells the first generation is translated onto need clauses too.
+            let (margin_x, margin_y) = (1 + shift_x.abs(), 1 + shift_y.abs());
+            for y in -margin_y..height as i32 + margin_y {
+                for x in -margin_x..width as i32 + margin_x {
+                    let mut neighbours = [Term::Fixed(false); 8];
+                    for (neighbour, (ox, oy)) in neighbours.iter_mut().zip(offsets) {
+                        *neighbour = self.term(t, x + ox, y + oy);
+                    }
+                    let next = self.term(next_t, x - shift_x, y - shift_y);
+                    encode_transition(
+                        &mut solver,
+                        self.rule,
+                        self.term(t, x, y),
+                        &neighbours,
+                        next,
+                    );
+                }
+            }
+        }
+
+        // The first generation is not empty.
+        let alive: Vec<Lit> = self.generations[0]
+            .iter()
+            .filter_map(|term| match term {
+                Term::Free(lit) => Some(*lit),
+                Term::Fixed(_) => None,
+            })
+            .collect();
+        solver.add_clause(&alive);
+
+        // It does not repeat after any proper divisor of the period. Ruling
+        // out the largest ones, `period / prime`, rules out the rest.
+        for prime in (2..=self.period).filter(|&n| self.period.is_multiple_of(n) && is_prime(n)) {
+            let q = self.period / prime;
+            let (shift_x, shift_y) = (self.dx * q as i32, self.dy * q as i32);
+            if shift_x % self.period as i32 != 0 || shift_y % self.period as i32 != 0 {
+                continue;
+            }
+            let (shift_x, shift_y) = (shift_x / self.period as i32, shift_y / self.period as i32);
+            self.encode_difference(&mut solver, q as usize, shift_x, shift_y);
+        }
+
+        self.solver = Some(solver);
+    }
+
+    /// The state of cell `(x, y)` in generation `t`, dead outside the box.
+    fn term(&self, t: u32, x: i32, y: i32) -> Term {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return Term::Fixed(false);
+        }
+        self.generations[t as usize][(y as u32 * self.width + x as u32) as usize]
+    }
+
+    /// Requires generation `t` to differ from the first translated by
+    /// `(shift_x, shift_y)`.
+    fn encode_difference(&self, solver: &mut Solver, t: usize, shift_x: i32, shift_y: i32) {
+        let mut differences = Vec::new();
+        let (width, height) = (self.width as i32, self.height as i32);
+        for y in -shift_y.abs()..height + shift_y.abs() {
+            for x in -shift_x.abs()..width + shift_x.abs() {
+                let a = self.term(0, x, y);
+                let b = self.term(t as u32, x + shift_x, y + shift_y);
+                let differ = match (a, b) {
+                    (Term::Fixed(false), Term::Fixed(false)) => continue,
+                    (Term::Fixed(false), Term::Free(lit))
+                    | (Term::Free(lit), Term::Fixed(false)) => lit,
+                    (Term::Free(a), Term::Free(b)) => {
+                        let differ = Lit::new(solver.new_var(), true);
+                        solver.add_clause(&[!differ, a, b]);
+                        solver.add_clause(&[!differ, !a, !b]);
+                        differ
+                    }
+                    _ => unreachable!(),
+                };
+                differences.push(differ);
+            }
+        }
+        solver.add_clause(&differences);
+    }
+
+    /// Generation `t` of the last solution.
+    fn phase(&self, t: usize) -> Pattern {
+        let solver = self.solver.as_ref().unwrap();
+        let cells = self.generations[t]
+            .iter()
+            .map(|term| match term {
+                Term::Free(lit) if solver.model_value(*lit) => Cell::Alive,
+                Term::Free(_) => Cell::Dead,
+                Term::Fixed(value) if *value => Cell::Alive,
+                Term::Fixed(_) => Cell::Dead,
+            })
+            .collect();
+        crop(&Pattern::new(self.width, self.height, cells))
+    }
+
+    /// Rules out `pattern` as the first generation anywhere in the box.
+    fn exclude(&mut self, pattern: &Pattern) {
+        let solver = self.solver.as_mut().unwrap();
+        if pattern.width() > self.width || pattern.height() > self.height {
+            return;
+        }
+        for oy in 0..=self.height - pattern.height() {
+            for ox in 0..=self.width - pattern.width() {
+                let mut clause = Vec::new();
+                for y in 0..self.height {
+                    for x in 0..self.width {
+                        let inside = x >= ox
+                            && y >= oy
+                            && x - ox < pattern.width()
+                            && y - oy < pattern.height();
+                        let alive = inside && pattern.get(x - ox, y - oy) == Cell::Alive;
+                        if let Term::Free(lit) = self.generations[0][(y * self.width + x) as usize]
+                        {
+                            clause.push(if alive { !lit } else { lit });
+                        }
+                    }
+                }
+                solver.add_clause(&clause);
+            }
+        }
+    }
+}
+
+/// Trims dead rows and columns from the edges of `pattern`.
+fn crop(pattern: &Pattern) -> Pattern {
+    let alive: Vec<(u32, u32)> = (0..pattern.height())
+        .flat_map(|y| (0..pattern.width()).map(move |x| (x, y)))
+        .filter(|&(x, y)| pattern.get(x, y) == Cell::Alive)
+        .collect();
+    let min_x = alive.iter().map(|&(x, _)| x).min().unwrap_or(0);
+    let min_y = alive.iter().map(|&(_, y)| y).min().unwrap_or(0);
+    let max_x = alive.iter().map(|&(x, _)| x + 1).max().unwrap_or(0);
+    let max_y = alive.iter().map(|&(_, y)| y + 1).max().unwrap_or(0);
+
+    let mut cropped = Pattern::empty(max_x - min_x, max_y - min_y);
+    for (x, y) in alive {
+        cropped.set(x - min_x, y - min_y, Cell::Alive);
+    }
+    cropped
+}
+
+fn is_prime(n: u32) -> bool {
+    n >= 2
+        && (2..n)
+            .take_while(|d| d * d <= n)
+            .all(|d| !n.is_multiple_of(d))
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// Starts a `PatternSearch` under this universe's rule.
+    pub fn pattern_search(
+        &self,
+        width: u32,
+        height: u32,
+        period: u32,
+        dx: i32,
+        dy: i32,
+    ) -> Result<PatternSearch, JsValue> {
+        let mut search = PatternSearch::new(width, height, period, dx, dy)?;
+        search.rule = self.rule;
+        Ok(search)
+    }
+}
@@ -0,0 +1,194 @@
+use wasm_bindgen::prelude::*;
+
+use crate::life_counter_combined::{Cell, Universe};
+use crate::pattern::Pattern;
+use crate::population::BoundingBox;
+
+/// How pasted cells combine with the cells already on the board.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PasteMode {
+    /// Live cells are added, nothing is cleared.
+    Or = 0,
+    /// Only cells alive on both the board and the clipboard stay alive.
+    And = 1,
+    /// Live cells toggle the cells under them.
+    Xor = 2,
+    /// The clipboard overwrites its whole rectangle.
+    Copy = 3,
+}
+
+impl PasteMode {
+    fn combine(self, board: Cell, pasted: Cell) -> Cell {
+        let alive = match self {
+            PasteMode::Or => board == Cell::Alive || pasted == Cell::Alive,
+            PasteMode::And => board == Cell::Alive && pasted == Cell::Alive,
+            PasteMode::Xor => (board == Cell::Alive) != (pasted == Cell::Alive),
+            PasteMode::Copy => pasted == Cell::Alive,
+        };
+        if alive {
+            Cell::Alive
+        } else {
+            Cell::Dead
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// Selects the rectangle with its top-left corner at `(x, y)`, wrapping
+    /// around the edges.
+    pub fn select(&mut self, x: u32, y: u32, width: u32, height: u32) -> Result<(), JsValue> {
+        if x >= self.width || y >= self.height {
+            return Err(JsValue::from_str(&format!(
+                "selection corner ({}, {}) is outside the {}x{} board",
+                x, y, self.width, self.height
+            )));
+        }
+        if width == 0 || height == 0 || width > self.width || height > self.height {
+            return Err(JsValue::from_str(&format!(
+                "a {}x{} selection does not fit on the {}x{} board",
+                width, height, self.width, self.height
+            )));
+        }
+        self.selection = Some(BoundingBox {
+            x,
+            y,
+            width,
+            height,
+        });
+        Ok(())
+    }
+
+    pub fn select_all(&mut self) {
+        self.selection = Some(BoundingBox {
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+        });
+    }
+
+    pub fn selection(&self) -> Option<BoundingBox> {
+        self.selection
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection = None;
+    }
+
+    /// Copies the selected cells to the clipboard. Returns false if nothing
+    /// is selected.
+    pub fn copy_selection(&mut self) -> bool {
+        match self.selection {
+            Some(selection) => {
+                self.clipboard = Some(self.read_region(selection));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Copies the selected cells to the clipboard, then clears them as a
+    /// single undoable edit. Returns false if nothing is selected.
+    pub fn cut_selection(&mut self) -> bool {
+        let selection = match self.selection {
+            Some(selection) => selection,
+            None => return false,
+        };
+        self.copy_selection();
+        let blank = Pattern::empty(selection.width, selection.height);
+        self.write_pattern(&blank, selection.x, selection.y, PasteMode::Copy);
+        true
+    }
+
+    /// Combines the clipboard into the board with its top-left corner at
+    /// `(x, y)`, wrapping around the edges, as a single undoable edit. Any
+    /// part of the clipboard beyond the board's size is left out. Returns
+    /// false if the clipboard is empty.
+    pub fn paste(&mut self, x: u32, y: u32, mode: PasteMode) -> bool {
+        match self.clipboard.take() {
+            Some(clipboard) => {
+                self.write_pattern(&clipboard, x, y, mode);
+                self.clipboard = Some(clipboard);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn has_clipboard(&self) -> bool {
+        self.clipboard.is_some()
+    }
+
+    /// The clipboard as an RLE file, if it holds anything.
+    pub fn clipboard_rle(&self) -> Option<String> {
+        self.clipboard
+            .as_ref()
+            .map(|pattern| pattern.to_rle(self.rule))
+    }
+
+    /// Replaces the clipboard with a pattern read from RLE.
+    pub fn set_clipboard_rle(&mut self, rle: &str) -> Result<(), JsValue> {
+        let pattern = Pattern::from_rle(rle).map_err(|err| JsValue::from_str(&err))?;
+        self.clipboard = Some(pattern);
+        Ok(())
+    }
+}
+
+impl Universe {
+    pub fn clipboard(&self) -> Option<&Pattern> {
+        self.clipboard.as_ref()
+    }
+
+    /// The cells in `region`, wrapping around the edges.
+    pub(crate) fn read_region(&self, region: BoundingBox) -> Pattern {
+        let mut pattern = Pattern::empty(region.width, region.height);
+        for dy in 0..region.height {
+            for dx in 0..region.width {
+                let x = (region.x + dx) % self.width;
+                let y = (region.y + dy) % self.height;
+                pattern.set(dx, dy, self.get(x, y));
+            }
+        }
+        pattern
+    }
+
+    /// Combines `pattern` into the board with its top-left corner at
+    /// `(x, y)`, wrapping around the edges, as a single undoable edit. Rows
+    /// and columns past the board's own height and width are clipped off so
+    /// the pattern never wraps over itself.
+    pub(crate) fn write_pattern(&mut self, pattern: &Pattern, x: u32, y: u32, mode: PasteMode) {
+        let (x, y) = (x % self.width, y % self.height);
+        self.begin_edit();
+        for py in 0..pattern.height().min(self.height) {
+            for px in 0..pattern.width().min(self.width) {
+                let (bx, by) = ((x + px) % self.width, (y + py) % self.height);
+                let cell = mode.combine(self.get(bx, by), pattern.get(px, py));
+                self.set(bx, by, cell);
+            }
+        }
+        self.end_edit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oversized_clipboard() {
+        // Ten columns, the first eight alive, on a board eight wide.
+        let mut clipboard = Pattern::empty(10, 1);
+        for x in 0..8 {
+            clipboard.set(x, 0, Cell::Alive);
+        }
+        let mut universe = Universe::empty(8, 8, 0);
+        universe.recalculate();
+        universe.clipboard = Some(clipboard);
+
+        assert!(universe.paste(5, 3, PasteMode::Copy));
+        assert_eq!(universe.population(), 8);
+        assert!((0..8).all(|x| universe.get(x, 3) == Cell::Alive));
+    }
+}
@@ -0,0 +1,214 @@
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::builder::validate;
+use crate::life_counter_combined::Universe;
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+use crate::snapshot::{check_dimensions, Snapshot};
+
+/// Rules are written in their `B3/S23` notation.
+impl Serialize for Rule {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Rule, D::Error> {
+        let rule = String::deserialize(deserializer)?;
+        rule.parse().map_err(de::Error::custom)
+    }
+}
+
+// Cells are stored as an RLE body rather than an array of enums, which keeps
+// a 512x512 board to a few kilobytes of JSON.
+#[derive(Serialize, Deserialize)]
+struct SnapshotRepr {
+    width: u32,
+    height: u32,
+    rule: Rule,
+    tick_count: u32,
+    cells: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    colours: Option<Vec<u32>>,
+}
+
+impl Serialize for Snapshot {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SnapshotRepr {
+            width: self.width,
+            height: self.height,
+            rule: self.rule,
+            tick_count: self.tick_count,
+            cells: Pattern::new(self.width, self.height, self.cells.clone()).rle_body(),
+            colours: self.colours.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Snapshot {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Snapshot, D::Error> {
+        let repr = SnapshotRepr::deserialize(deserializer)?;
+
+        let len = check_dimensions(repr.width, repr.height).map_err(de::Error::custom)?;
+        if repr
+            .colours
+            .as_ref()
+            .is_some_and(|colours| colours.len() != len)
+        {
+            return Err(de::Error::custom(
+                "colour map does not match the dimensions",
+            ));
+        }
+        let cells = Pattern::from_rle_body(repr.width, repr.height, &repr.cells)
+            .map_err(de::Error::custom)?
+            .into_cells();
+
+        Ok(Snapshot {
+            width: repr.width,
+            height: repr.height,
+            rule: repr.rule,
+            tick_count: repr.tick_count,
+            cells,
+            colours: repr.colours,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct UniverseRef<'a> {
+    cell_size: u32,
+    #[serde(flatten)]
+    snapshot: &'a Snapshot,
+}
+
+#[derive(Deserialize)]
+struct UniverseRepr {
+    cell_size: u32,
+    #[serde(flatten)]
+    snapshot: Snapshot,
+}
+
+/// A universe is serialised as its snapshot plus the cell size.
+impl Serialize for Universe {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        UniverseRef {
+            cell_size: self.cell_size,
+            snapshot: &self.to_snapshot(false),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Universe {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Universe, D::Error> {
+        let repr = UniverseRepr::deserialize(deserializer)?;
+        let snapshot = repr.snapshot;
+        validate(snapshot.width, snapshot.height, repr.cell_size).map_err(de::Error::custom)?;
+
+        let mut universe = Universe::empty(snapshot.width, snapshot.height, repr.cell_size);
+        universe.load_snapshot(snapshot);
+        Ok(universe)
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// Converts the universe into a plain JS object, suitable for
+    /// `JSON.stringify`.
+    pub fn to_js(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(self).map_err(Into::into)
+    }
+
+    pub fn from_js(value: JsValue) -> Result<Universe, JsValue> {
+        serde_wasm_bindgen::from_value(value).map_err(Into::into)
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::life_counter_combined::Cell;
+
+    #[test]
+    fn rule_round_trip() {
+        let rule: Rule = "B36/S23".parse().unwrap();
+        let value = serde_json::to_value(rule).unwrap();
+        assert_eq!(value, json!("B36/S23"));
+        assert_eq!(serde_json::from_value::<Rule>(value).unwrap(), rule);
+        assert!(serde_json::from_value::<Rule>(json!("B3/S99")).is_err());
+    }
+
+    #[test]
+    fn snapshot_round_trip() {
+        let mut universe = Universe::new(24, 16, 1);
+        universe.tick();
+        let snapshot = universe.to_snapshot(true);
+
+        let value = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(value["width"], 24);
+        assert_eq!(value["tick_count"], 1);
+        assert!(value["cells"].as_str().unwrap().ends_with('!'));
+        assert_eq!(value["colours"].as_array().unwrap().len(), 24 * 16);
+        assert_eq!(serde_json::from_value::<Snapshot>(value).unwrap(), snapshot);
+
+        // Without colours the field is left out altogether.
+        let value = serde_json::to_value(universe.to_snapshot(false)).unwrap();
+        assert!(value.get("colours").is_none());
+    }
+
+    #[test]
+    fn universe_round_trip() {
+        let mut universe = Universe::new(24, 16, 3);
+        for y in 0..16 {
+            universe.set(0, y, Cell::Dead);
+        }
+        for _ in 0..3 {
+            universe.tick();
+        }
+
+        let value = serde_json::to_value(&universe).unwrap();
+        assert_eq!(value["cell_size"], 3);
+        let restored: Universe = serde_json::from_value(value).unwrap();
+        assert_eq!((restored.width, restored.height), (24, 16));
+        assert_eq!(restored.cell_size, 3);
+        assert_eq!(restored.tick_count, 3);
+        assert_eq!(restored.cells, universe.cells);
+        assert_eq!(restored.canvas, universe.canvas);
+    }
+
+    fn repr(width: u32, height: u32, cell_size: u32) -> serde_json::Value {
+        json!({
+            "cell_size": cell_size,
+            "width": width,
+            "height": height,
+            "rule": "B3/S23",
+            "tick_count": 0,
+            "cells": "!",
+        })
+    }
+
+    #[test]
+    fn invalid_universes() {
+        assert!(serde_json::from_value::<Universe>(repr(8, 8, 1)).is_ok());
+        assert!(serde_json::from_value::<Universe>(repr(0, 8, 1)).is_err());
+        assert!(serde_json::from_value::<Universe>(repr(512, 512, 200)).is_err());
+        assert!(serde_json::from_value::<Universe>(repr(1 << 20, 1 << 20, 0)).is_err());
+
+        let mut value = repr(8, 8, 1);
+        value["colours"] = json!(vec![0xFF000000u32; 63]);
+        assert!(serde_json::from_value::<Universe>(value.clone()).is_err());
+        value["colours"] = json!(vec![0xFF000000u32; 64]);
+        assert!(serde_json::from_value::<Universe>(value).is_ok());
+
+        let mut value = repr(8, 8, 1);
+        value["cells"] = json!("9o!");
+        assert!(serde_json::from_value::<Universe>(value).is_err());
+    }
+}
@@ -0,0 +1,440 @@
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+use crate::builder::{validate, MAX_CELLS};
+use crate::life_counter_combined::{Cell, Universe};
+use crate::rule::Rule;
+
+// Binary snapshot layout, all integers little-endian:
+//
+//   magic       4 bytes  "GOLS"
+//   version     u8
+//   flags       u8       bit 0 set when a colour map follows the cells
+//   width       u32
+//   height      u32
+//   birth       u16      rule birth mask
+//   survival    u16      rule survival mask
+//   topology    u8       0 = torus
+//   tick_count  u32
+//   cells_len   u32      length of the compressed cell data
+//   cells       PackBits-compressed cells, bit-packed 8 per byte, LSB first
+//   colours     width * height u32 (only when flag bit 0 is set)
+const MAGIC: &[u8; 4] = b"GOLS";
+const VERSION: u8 = 1;
+const FLAG_COLOURS: u8 = 1;
+const TOPOLOGY_TORUS: u8 = 0;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnsupportedTopology(u8),
+    InvalidDimensions(u32, u32),
+    Truncated,
+    CorruptCells,
+    TrailingData,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::BadMagic => write!(f, "not a universe snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {}", version)
+            }
+            SnapshotError::UnsupportedTopology(topology) => {
+                write!(f, "unsupported snapshot topology {}", topology)
+            }
+            SnapshotError::InvalidDimensions(width, height) => {
+                write!(f, "invalid snapshot dimensions {}x{}", width, height)
+            }
+            SnapshotError::Truncated => write!(f, "snapshot is truncated"),
+            SnapshotError::CorruptCells => write!(f, "snapshot cell data is corrupt"),
+            SnapshotError::TrailingData => write!(f, "snapshot has data after its end"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+/// A decoded snapshot of a universe's state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    pub width: u32,
+    pub height: u32,
+    pub rule: Rule,
+    pub tick_count: u32,
+    pub cells: Vec<Cell>,
+    pub colours: Option<Vec<u32>>,
+}
+
+impl Snapshot {
+    pub fn encode(&self) -> Vec<u8> {
+        let packed = compress(&pack_cells(&self.cells));
+
+        let mut bytes = Vec::with_capacity(27 + packed.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(if self.colours.is_some() {
+            FLAG_COLOURS
+        } else {
+            0
+        });
+        bytes.extend_from_slice(&self.width.to_le_bytes());
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        bytes.extend_from_slice(&self.rule.birth().to_le_bytes());
+        bytes.extend_from_slice(&self.rule.survival().to_le_bytes());
+        bytes.push(TOPOLOGY_TORUS);
+        bytes.extend_from_slice(&self.tick_count.to_le_bytes());
+        bytes.extend_from_slice(&(packed.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&packed);
+
+        if let Some(colours) = &self.colours {
+            for colour in colours {
+                bytes.extend_from_slice(&colour.to_le_bytes());
+            }
+        }
+
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Snapshot, SnapshotError> {
+        let mut reader = Reader { bytes, pos: 0 };
+
+        if reader.take(4)? != MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let flags = reader.u8()?;
+        let width = reader.u32()?;
+        let height = reader.u32()?;
+        let len = check_dimensions(width, height)?;
+        let birth = reader.u16()?;
+        let survival = reader.u16()?;
+        let topology = reader.u8()?;
+        if topology != TOPOLOGY_TORUS {
+            return Err(SnapshotError::UnsupportedTopology(topology));
+        }
+        let tick_count = reader.u32()?;
+
+        let packed_len = reader.u32()? as usize;
+        let packed = decompress(reader.take(packed_len)?, len.div_ceil(8))?;
+        let cells = unpack_cells(&packed, len);
+
+        let colours = if flags & FLAG_COLOURS != 0 {
+            let raw = reader.take(len.checked_mul(4).ok_or(SnapshotError::Truncated)?)?;
+            Some(
+                raw.chunks_exact(4)
+                    .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+                    .collect(),
+            )
+        } else {
+            None
+        };
+        if reader.pos != bytes.len() {
+            return Err(SnapshotError::TrailingData);
+        }
+
+        Ok(Snapshot {
+            width,
+            height,
+            rule: Rule::new(birth, survival),
+            tick_count,
+            cells,
+            colours,
+        })
+    }
+}
+
+/// Returns the number of cells in a `width` x `height` world, rejecting empty
+/// worlds and ones with more than `MAX_CELLS` cells.
+pub(crate) fn check_dimensions(width: u32, height: u32) -> Result<usize, SnapshotError> {
+    let len = width as u64 * height as u64;
+    if len == 0 || len > MAX_CELLS {
+        return Err(SnapshotError::InvalidDimensions(width, height));
+    }
+    Ok(len as usize)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], SnapshotError> {
+        let end = self.pos.checked_add(n).ok_or(SnapshotError::Truncated)?;
+        let slice = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or(SnapshotError::Truncated)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, SnapshotError> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+}
+
+/// Packs cells 8 per byte, least significant bit first.
+pub(crate) fn pack_cells(cells: &[Cell]) -> Vec<u8> {
+    let mut packed = vec![0u8; cells.len().div_ceil(8)];
+    for (i, &cell) in cells.iter().enumerate() {
+        if cell == Cell::Alive {
+            packed[i / 8] |= 1 << (i % 8);
+        }
+    }
+    packed
+}
+
+pub(crate) fn unpack_cells(packed: &[u8], len: usize) -> Vec<Cell> {
+    (0..len)
+        .map(|i| {
+            if (packed[i / 8] >> (i % 8)) & 1 == 1 {
+                Cell::Alive
+            } else {
+                Cell::Dead
+            }
+        })
+        .collect()
+}
+
+// PackBits run-length encoding: a header byte `n` below 128 is followed by
+// `n + 1` literal bytes, a header above 128 repeats the next byte `257 - n`
+// times.
+fn compress(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        let mut run = 1;
+        while i + run < data.len() && run < 128 && data[i + run] == data[i] {
+            run += 1;
+        }
+
+        if run > 1 {
+            out.push((257 - run) as u8);
+            out.push(data[i]);
+            i += run;
+            continue;
+        }
+
+        let start = i;
+        while i < data.len() && i - start < 128 && !(i + 1 < data.len() && data[i + 1] == data[i]) {
+            i += 1;
+        }
+        out.push((i - start - 1) as u8);
+        out.extend_from_slice(&data[start..i]);
+    }
+    out
+}
+
+fn decompress(data: &[u8], expected: usize) -> Result<Vec<u8>, SnapshotError> {
+    let mut out = Vec::with_capacity(expected);
+    let mut i = 0;
+    while i < data.len() {
+        let header = data[i] as usize;
+        i += 1;
+        if header < 128 {
+            let literal = data
+                .get(i..i + header + 1)
+                .ok_or(SnapshotError::CorruptCells)?;
+            out.extend_from_slice(literal);
+            i += header + 1;
+        } else if header > 128 {
+            let byte = *data.get(i).ok_or(SnapshotError::CorruptCells)?;
+            out.resize(out.len() + 257 - header, byte);
+            i += 1;
+        }
+        if out.len() > expected {
+            return Err(SnapshotError::CorruptCells);
+        }
+    }
+
+    if out.len() != expected {
+        return Err(SnapshotError::CorruptCells);
+    }
+    Ok(out)
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// Serialises the cells, rule and tick count into a compact binary
+    /// snapshot which can be handed back to `restore`.
+    pub fn snapshot(&self) -> Vec<u8> {
+        self.to_snapshot(false).encode()
+    }
+
+    /// Like `snapshot`, but also stores the colour map.
+    pub fn snapshot_with_colours(&self) -> Vec<u8> {
+        self.to_snapshot(true).encode()
+    }
+
+    /// Restores a snapshot, resizing the universe if its dimensions differ.
+    /// Fails, leaving the universe alone, if the snapshot is too large to
+    /// draw at the current cell size.
+    pub fn restore(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+        let snapshot =
+            Snapshot::decode(bytes).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        validate(snapshot.width, snapshot.height, self.cell_size)
+            .map_err(|err| JsValue::from_str(&err.to_string()))?;
+        self.load_snapshot(snapshot);
+        Ok(())
+    }
+}
+
+impl Universe {
+    pub(crate) fn to_snapshot(&self, colours: bool) -> Snapshot {
+        Snapshot {
+            width: self.width,
+            height: self.height,
+            rule: self.rule,
+            tick_count: self.tick_count,
+            cells: self.cells.clone(),
+            colours: if colours {
+                Some(self.colour_canvas.clone())
+            } else {
+                None
+            },
+        }
+    }
+
+    pub(crate) fn load_snapshot(&mut self, snapshot: Snapshot) {
+        let resized = snapshot.width != self.width || snapshot.height != self.height;
+        if resized {
+            self.reallocate(snapshot.width, snapshot.height);
+        }
+
+        self.edit_history.clear();
+        self.rule = snapshot.rule;
+        self.tick_count = snapshot.tick_count;
+        self.cells = snapshot.cells;
+        match snapshot.colours {
+            Some(colours) => self.colour_canvas = colours,
+            None if resized => self.reset_colour_canvas(),
+            None => {}
+        }
+
+        if let Some(timeline) = &mut self.timeline {
+            timeline.reset(self.tick_count, &self.cells);
+        }
+        self.recalculate();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> Snapshot {
+        let cells = (0..40 * 24)
+            .map(|i| if i % 7 < 3 { Cell::Alive } else { Cell::Dead })
+            .collect();
+        Snapshot {
+            width: 40,
+            height: 24,
+            rule: Rule::new(1 << 3 | 1 << 6, 1 << 2 | 1 << 3),
+            tick_count: 17,
+            cells,
+            colours: Some((0..40 * 24).map(|i| 0xFF000000 | i).collect()),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let snapshot = snapshot();
+        assert_eq!(Snapshot::decode(&snapshot.encode()), Ok(snapshot.clone()));
+
+        let plain = Snapshot {
+            colours: None,
+            ..snapshot
+        };
+        assert_eq!(Snapshot::decode(&plain.encode()), Ok(plain));
+    }
+
+    #[test]
+    fn universe_round_trip() {
+        let mut universe = Universe::new(40, 24, 2);
+        for _ in 0..5 {
+            universe.tick();
+        }
+        let mut restored = Universe::new(16, 16, 3);
+        restored.restore(&universe.snapshot()).unwrap();
+
+        assert_eq!((restored.width, restored.height), (40, 24));
+        assert_eq!(restored.tick_count, 5);
+        assert_eq!(restored.cells, universe.cells);
+        universe.tick();
+        restored.tick();
+        assert_eq!(restored.cells, universe.cells);
+    }
+
+    #[test]
+    fn truncated() {
+        let bytes = snapshot().encode();
+        for len in [0, 3, 10, 26, 30, bytes.len() - 1] {
+            assert_eq!(
+                Snapshot::decode(&bytes[..len]),
+                Err(SnapshotError::Truncated),
+                "{} bytes",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn corrupt() {
+        let mut bytes = snapshot().encode();
+        bytes[0] = b'X';
+        assert_eq!(Snapshot::decode(&bytes), Err(SnapshotError::BadMagic));
+
+        let mut bytes = snapshot().encode();
+        bytes[4] = 9;
+        assert_eq!(
+            Snapshot::decode(&bytes),
+            Err(SnapshotError::UnsupportedVersion(9))
+        );
+
+        // A run longer than the board.
+        let mut bytes = snapshot().encode();
+        bytes[27] = 129;
+        assert_eq!(Snapshot::decode(&bytes), Err(SnapshotError::CorruptCells));
+
+        let mut bytes = snapshot().encode();
+        bytes.push(0);
+        assert_eq!(Snapshot::decode(&bytes), Err(SnapshotError::TrailingData));
+    }
+
+    #[test]
+    fn oversized() {
+        let mut bytes = snapshot().encode();
+        bytes[6..10].copy_from_slice(&u32::MAX.to_le_bytes());
+        bytes[10..14].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            Snapshot::decode(&bytes),
+            Err(SnapshotError::InvalidDimensions(u32::MAX, u32::MAX))
+        );
+
+        let mut bytes = snapshot().encode();
+        bytes[6..10].copy_from_slice(&(1u32 << 14).to_le_bytes());
+        bytes[10..14].copy_from_slice(&(1u32 << 13).to_le_bytes());
+        assert_eq!(
+            Snapshot::decode(&bytes),
+            Err(SnapshotError::InvalidDimensions(1 << 14, 1 << 13))
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::builder::validate;
+use crate::census::CensusObject;
+use crate::life_counter_combined::{Cell, Universe};
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+use crate::run::StopCondition;
+use crate::utils::SplitMix64;
+
+const SOUP_SIZE: u32 = 16;
+
+// Glider, LWSS, MWSS and HWSS are common enough not to be flagged.
+const COMMON_SPACESHIPS: [&str; 4] = ["xq4_153", "xq4_6frc", "xq4_27dee6", "xq4_27deee6"];
+
+/// Symmetry imposed on generated soups.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Symmetry {
+    /// No symmetry.
+    C1 = 0,
+    /// Unchanged by a 180 degree rotation.
+    C2 = 1,
+    /// Unchanged by a 90 degree rotation.
+    C4 = 2,
+    /// Mirrored left to right.
+    D2 = 3,
+    /// Mirrored left to right and top to bottom.
+    D4 = 4,
+    /// Mirrored along both axes and both diagonals.
+    D8 = 5,
+}
+
+impl Symmetry {
+    /// Whether the symmetry maps a `width` by `height` box onto itself.
+    pub(crate) fn fits(&self, width: u32, height: u32) -> bool {
+        width == height || !matches!(self, Symmetry::C4 | Symmetry::D8)
+    }
+
+    /// The cell of a `width` by `height` box whose value decides `(x, y)`:
+    /// the first of its images under the symmetry.
+    pub(crate) fn representative(&self, x: u32, y: u32, width: u32, height: u32) -> (u32, u32) {
+        let (w, h) = (width - 1, height - 1);
+        let images: &[(u32, u32)] = match self {
+            Symmetry::C2 => &[(x, y), (w - x, h - y)],
+            Symmetry::C4 => &[(x, y), (w - y, x), (w - x, h - y), (y, h - x)],
+            Symmetry::D2 => &[(x, y), (w - x, y)],
+            Symmetry::D4 => &[(x, y), (w - x, y), (x, h - y), (w - x, h - y)],
+            Symmetry::D8 => &[
+                (x, y),
+                (w - x, y),
+                (x, h - y),
+                (w - x, h - y),
+                (y, x),
+                (w - y, x),
+                (y, h - x),
+                (w - y, h - x),
+            ],
+            Symmetry::C1 => &[(x, y)],
+        };
+        *images.iter().min_by_key(|&&(x, y)| (y, x)).unwrap()
+    }
+}
+
+/// A rare object and the soup which produced it.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RareFind {
+    code: String,
+    soup: u64,
+}
+
+#[wasm_bindgen]
+impl RareFind {
+    pub fn code(&self) -> String {
+        self.code.clone()
+    }
+
+    /// The soup number, which with the search seed reproduces the soup
+    /// through `SoupSearch::soup_rle`.
+    pub fn soup(&self) -> u64 {
+        self.soup
+    }
+}
+
+/// A headless soup search: random 16x16 soups are run to stabilisation in a
+/// padded torus and the resulting objects are tallied by apgcode.
+#[wasm_bindgen]
+pub struct SoupSearch {
+    seed: u64,
+    symmetry: Symmetry,
+    rule: Rule,
+    torus_size: u32,
+    max_generations: u32,
+    max_period: u32,
+    rare_period: u32,
+    next_soup: u64,
+    unstabilised: u64,
+    counts: HashMap<String, u64>,
+    rare: Vec<RareFind>,
+}
+
+#[wasm_bindgen]
+impl SoupSearch {
+    pub fn new(seed: u64, symmetry: Symmetry) -> SoupSearch {
+        SoupSearch {
+            seed,
+            symmetry,
+            rule: Rule::CONWAY,
+            torus_size: 128,
+            max_generations: 20_000,
+            max_period: 1000,
+            rare_period: 4,
+            next_soup: 0,
+            unstabilised: 0,
+            counts: HashMap::new(),
+            rare: Vec::new(),
+        }
+    }
+
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), JsValue> {
+        self.rule = rule
+            .parse()
+            .map_err(|err: String| JsValue::from_str(&err))?;
+        Ok(())
+    }
+
+    /// Side of the torus soups are run in. Larger tori give escaping
+    /// spaceships longer before they wrap around into the debris. Throws a
+    /// `DimensionError` if the torus would have more than `MAX_CELLS` cells.
+    pub fn set_torus_size(&mut self, size: u32) -> Result<(), JsValue> {
+        let size = size.max(SOUP_SIZE + 8);
+        validate(size, size, 0)?;
+        self.torus_size = size;
+        Ok(())
+    }
+
+    /// Soups which have not settled by this generation are skipped.
+    pub fn set_max_generations(&mut self, generations: u32) {
+        self.max_generations = generations;
+    }
+
+    /// Oscillators of at least this period are flagged as rare.
+    pub fn set_rare_period(&mut self, period: u32) {
+        self.rare_period = period;
+    }
+
+    /// Runs the next `count` soups.
+    pub fn run(&mut self, count: u32) {
+        let mut universe = Universe::empty(self.torus_size, self.torus_size, 0);
+        universe.rule = self.rule;
+        universe.set_cycle_window(self.max_period as usize);
+
+        for _ in 0..count {
+            let soup = self.next_soup;
+            self.next_soup += 1;
+            self.run_soup(&mut universe, soup);
+        }
+    }
+
+    /// The number of soups searched so far.
+    pub fn soups(&self) -> u64 {
+        self.next_soup
+    }
+
+    /// Soups which were still changing after the generation limit.
+    pub fn unstabilised(&self) -> u64 {
+        self.unstabilised
+    }
+
+    /// One `code count` line per object type, most common first.
+    pub fn to_text(&self) -> String {
+        self.sorted_counts()
+            .iter()
+            .map(|(code, count)| format!("{} {}\n", code, count))
+            .collect()
+    }
+
+    pub fn rare_finds(&self) -> Vec<RareFind> {
+        self.rare.clone()
+    }
+
+    /// Regenerates soup number `soup` as an RLE pattern.
+    pub fn soup_rle(&self, soup: u64) -> String {
+        self.soup(soup).to_rle(self.rule)
+    }
+}
+
+impl SoupSearch {
+    pub fn sorted_counts(&self) -> Vec<(String, u64)> {
+        let mut counts: Vec<(String, u64)> = self
+            .counts
+            .iter()
+            .map(|(code, &count)| (code.clone(), count))
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+
+    fn soup(&self, soup: u64) -> Pattern {
+        let mut rng = SplitMix64::new(self.seed ^ SplitMix64::new(soup).next_u64());
+        let bits = [
+            rng.next_u64(),
+            rng.next_u64(),
+            rng.next_u64(),
+            rng.next_u64(),
+        ];
+
+        let mut pattern = Pattern::empty(SOUP_SIZE, SOUP_SIZE);
+        for y in 0..SOUP_SIZE {
+            for x in 0..SOUP_SIZE {
+                let (rx, ry) = self.symmetry.representative(x, y, SOUP_SIZE, SOUP_SIZE);
+                let bit = (ry * SOUP_SIZE + rx) as usize;
+                if (bits[bit / 64] >> (bit % 64)) & 1 == 1 {
+                    pattern.set(x, y, Cell::Alive);
+                }
+            }
+        }
+        pattern
+    }
+
+    fn run_soup(&mut self, universe: &mut Universe, soup: u64) {
+        let pattern = self.soup(soup);
+        let offset = (self.torus_size - SOUP_SIZE) / 2;
+        universe
+            .cells
+            .iter_mut()
+            .for_each(|cell| *cell = Cell::Dead);
+        for y in 0..SOUP_SIZE {
+            for x in 0..SOUP_SIZE {
+                let idx = universe.index(x + offset, y + offset);
+                universe.cells[idx] = pattern.get(x, y);
+            }
+        }
+        universe.tick_count = 0;
+        universe.recalculate();
+
+        let stopped = StopCondition::periodic(self.max_period);
+        if universe.run_until(&stopped, self.max_generations).is_none() {
+            self.unstabilised += 1;
+            return;
+        }
+
+        let census = universe.census(self.max_period);
+        for object in census.objects() {
+            *self.counts.entry(object.code.clone()).or_insert(0) += 1;
+            if self.is_rare(object) {
+                self.rare.push(RareFind {
+                    code: object.code.clone(),
+                    soup,
+                });
+            }
+        }
+    }
+
+    fn is_rare(&self, object: &CensusObject) -> bool {
+        match object.motion {
+            Some(motion) if motion.is_spaceship() => {
+                !COMMON_SPACESHIPS.contains(&object.code.as_str())
+            }
+            Some(motion) => motion.period >= self.rare_period,
+            // Never settled within the census window: a possible puffer,
+            // gun or very high period oscillator.
+            None => true,
+        }
+    }
+}
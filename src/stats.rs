@@ -0,0 +1,259 @@
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+use wasm_bindgen::prelude::*;
+
+use crate::life_counter_combined::{Cell, Universe};
+
+const DEFAULT_LIMIT: usize = 10_000;
+const CSV_HEADER: &str = "generation,population,births,deaths,bounding_box_area,density,entropy";
+
+/// Measurements of the board after one generation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Sample {
+    generation: u32,
+    population: u32,
+    births: u32,
+    deaths: u32,
+    bounding_box_area: u64,
+    density: f64,
+    entropy: f64,
+}
+
+/// A time series of board measurements, one sample per generation while
+/// collection is switched on.
+///
+/// Births and deaths are counted cell by cell during `tick`, only while
+/// collection is on. Everything else needs a pass over the board, so the
+/// collector costs nothing while it is off.
+pub(crate) struct Stats {
+    enabled: bool,
+    births: u32,
+    deaths: u32,
+    samples: VecDeque<Sample>,
+    limit: usize,
+}
+
+impl Stats {
+    pub(crate) fn new() -> Stats {
+        Stats {
+            enabled: false,
+            births: 0,
+            deaths: 0,
+            samples: VecDeque::new(),
+            limit: DEFAULT_LIMIT,
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    #[inline]
+    pub(crate) fn cell_changed(&mut self, cell: Cell) {
+        if !self.enabled {
+            return;
+        }
+        if cell == Cell::Alive {
+            self.births += 1;
+        } else {
+            self.deaths += 1;
+        }
+    }
+
+    fn push(&mut self, sample: Sample) {
+        if self
+            .samples
+            .back()
+            .is_some_and(|last| last.generation >= sample.generation)
+        {
+            self.truncate(sample.generation.saturating_sub(1));
+        }
+        self.samples.push_back(sample);
+        while self.samples.len() > self.limit {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Drops samples after `generation`, for when the universe has been
+    /// moved back in time.
+    pub(crate) fn truncate(&mut self, generation: u32) {
+        while self
+            .samples
+            .back()
+            .is_some_and(|last| last.generation > generation)
+        {
+            self.samples.pop_back();
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// Starts or stops recording a sample after every tick. Starting records
+    /// the current generation straight away, with no births or deaths.
+    pub fn set_stats_enabled(&mut self, enabled: bool) {
+        if enabled && !self.stats.enabled {
+            self.stats.births = 0;
+            self.stats.deaths = 0;
+            self.record_stats();
+        }
+        self.stats.enabled = enabled;
+    }
+
+    pub fn stats_enabled(&self) -> bool {
+        self.stats.enabled
+    }
+
+    /// Forgets every recorded sample.
+    pub fn clear_stats(&mut self) {
+        self.stats.samples.clear();
+    }
+
+    /// How many samples to keep, dropping the oldest beyond that.
+    pub fn set_stats_limit(&mut self, limit: usize) {
+        self.stats.limit = limit;
+        while self.stats.samples.len() > limit {
+            self.stats.samples.pop_front();
+        }
+    }
+
+    /// The recorded samples as CSV with a header row, oldest first.
+    /// Density is the fraction of the board alive, and entropy is the
+    /// Shannon entropy in bits of the cells' live-neighbour counts.
+    pub fn stats_csv(&self) -> String {
+        let mut csv = String::from(CSV_HEADER);
+        csv.push('\n');
+        for sample in &self.stats.samples {
+            writeln!(
+                csv,
+                "{},{},{},{},{},{},{}",
+                sample.generation,
+                sample.population,
+                sample.births,
+                sample.deaths,
+                sample.bounding_box_area,
+                sample.density,
+                sample.entropy,
+            )
+            .unwrap();
+        }
+        csv
+    }
+
+    /// The recorded samples as a JSON array of objects with the same fields
+    /// as `stats_csv`.
+    pub fn stats_json(&self) -> String {
+        let mut json = String::from("[");
+        for (i, sample) in self.stats.samples.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            write!(
+                json,
+                "{{\"generation\":{},\"population\":{},\"births\":{},\"deaths\":{},\
+                 \"bounding_box_area\":{},\"density\":{},\"entropy\":{}}}",
+                sample.generation,
+                sample.population,
+                sample.births,
+                sample.deaths,
+                sample.bounding_box_area,
+                sample.density,
+                sample.entropy,
+            )
+            .unwrap();
+        }
+        json.push(']');
+        json
+    }
+}
+
+impl Universe {
+    /// Samples the board as it stands, along with the births and deaths
+    /// counted since the last sample.
+    pub(crate) fn record_stats(&mut self) {
+        let bounding_box_area = self
+            .bounding_box()
+            .map_or(0, |bounds| bounds.width as u64 * bounds.height as u64);
+        let population = self.population.count;
+        let sample = Sample {
+            generation: self.tick_count,
+            population,
+            births: self.stats.births,
+            deaths: self.stats.deaths,
+            bounding_box_area,
+            density: population as f64 / self.cells.len().max(1) as f64,
+            entropy: self.neighbour_count_entropy(),
+        };
+        self.stats.births = 0;
+        self.stats.deaths = 0;
+        self.stats.push(sample);
+    }
+
+    fn neighbour_count_entropy(&self) -> f64 {
+        let mut histogram = [0u32; 9];
+        for &count in &self.cell_neighbour_count {
+            histogram[count as usize] += 1;
+        }
+        let total = self.cell_neighbour_count.len() as f64;
+        let entropy: f64 = histogram
+            .iter()
+            .filter(|&&n| n > 0)
+            .map(|&n| {
+                let p = n as f64 / total;
+                -p * p.log2()
+            })
+            .sum();
+        // Avoid reporting -0 for a uniform board.
+        entropy.max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blinker() -> Universe {
+        let mut universe = Universe::empty(16, 16, 0);
+        for x in 6..9 {
+            let idx = universe.index(x, 7);
+            universe.cells[idx] = Cell::Alive;
+        }
+        universe.recalculate();
+        universe
+    }
+
+    #[test]
+    fn births_and_deaths() {
+        let mut universe = blinker();
+        universe.set_stats_enabled(true);
+        universe.tick();
+        universe.tick();
+
+        let csv = universe.stats_csv();
+        let rows: Vec<Vec<&str>> = csv
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').collect())
+            .collect();
+        assert_eq!(rows.len(), 3);
+        // generation, population, births, deaths, bounding box area
+        assert_eq!(rows[0][..5], ["0", "3", "0", "0", "3"]);
+        assert_eq!(rows[1][..5], ["1", "3", "2", "2", "3"]);
+        assert_eq!(rows[2][..5], ["2", "3", "2", "2", "3"]);
+    }
+
+    #[test]
+    fn nothing_counted_while_disabled() {
+        let mut universe = blinker();
+        for _ in 0..100 {
+            universe.tick();
+        }
+        assert_eq!((universe.stats.births, universe.stats.deaths), (0, 0));
+        assert!(universe.stats.samples.is_empty());
+
+        universe.set_stats_enabled(true);
+        universe.tick();
+        assert_eq!(universe.stats.samples.back().unwrap().births, 2);
+    }
+}
@@ -0,0 +1,123 @@
+use wasm_bindgen::prelude::*;
+
+use crate::life_counter_combined::Universe;
+use crate::pattern::Pattern;
+use crate::population::BoundingBox;
+use crate::selection::PasteMode;
+
+/// A rotation or reflection of a rectangle of cells.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transform {
+    /// A quarter turn clockwise.
+    Rotate90 = 0,
+    Rotate180 = 1,
+    /// A quarter turn anticlockwise.
+    Rotate270 = 2,
+    /// Mirrors left and right.
+    FlipHorizontal = 3,
+    /// Mirrors top and bottom.
+    FlipVertical = 4,
+    /// Mirrors in the leading diagonal, swapping rows and columns.
+    Transpose = 5,
+}
+
+impl Transform {
+    fn swaps_axes(self) -> bool {
+        matches!(
+            self,
+            Transform::Rotate90 | Transform::Rotate270 | Transform::Transpose
+        )
+    }
+}
+
+impl Pattern {
+    /// A copy of the pattern with `transform` applied.
+    pub fn transformed(&self, transform: Transform) -> Pattern {
+        let (width, height) = (self.width(), self.height());
+        let (new_width, new_height) = if transform.swaps_axes() {
+            (height, width)
+        } else {
+            (width, height)
+        };
+
+        let mut pattern = Pattern::empty(new_width, new_height);
+        for y in 0..new_height {
+            for x in 0..new_width {
+                let (source_x, source_y) = match transform {
+                    Transform::Rotate90 => (y, height - 1 - x),
+                    Transform::Rotate180 => (width - 1 - x, height - 1 - y),
+                    Transform::Rotate270 => (width - 1 - y, x),
+                    Transform::FlipHorizontal => (width - 1 - x, y),
+                    Transform::FlipVertical => (x, height - 1 - y),
+                    Transform::Transpose => (y, x),
+                };
+                pattern.set(x, y, self.get(source_x, source_y));
+            }
+        }
+        pattern
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// Applies `transform` to the selected cells in place, as a single
+    /// undoable edit. The result keeps the selection's top-left corner, and
+    /// the selection is resized to fit it when rows and columns swap.
+    /// Returns false if nothing is selected.
+    pub fn transform_selection(&mut self, transform: Transform) -> Result<bool, JsValue> {
+        let selection = match self.selection {
+            Some(selection) => selection,
+            None => return Ok(false),
+        };
+        let transformed = self.read_region(selection).transformed(transform);
+        if transformed.width() > self.width || transformed.height() > self.height {
+            return Err(JsValue::from_str(&format!(
+                "a {}x{} selection does not fit on the {}x{} board once transformed",
+                selection.width, selection.height, self.width, self.height
+            )));
+        }
+
+        self.begin_edit();
+        let blank = Pattern::empty(selection.width, selection.height);
+        self.write_pattern(&blank, selection.x, selection.y, PasteMode::Copy);
+        self.write_pattern(&transformed, selection.x, selection.y, PasteMode::Copy);
+        self.end_edit();
+
+        self.selection = Some(BoundingBox {
+            width: transformed.width(),
+            height: transformed.height(),
+            ..selection
+        });
+        Ok(true)
+    }
+
+    /// Applies `transform` to the clipboard. Returns false if it is empty.
+    pub fn transform_clipboard(&mut self, transform: Transform) -> bool {
+        match &mut self.clipboard {
+            Some(clipboard) => {
+                *clipboard = clipboard.transformed(transform);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves every cell by `(dx, dy)`, wrapping around the edges, as a
+    /// single undoable edit.
+    pub fn shift(&mut self, dx: i32, dy: i32) {
+        let dx = dx.rem_euclid(self.width as i32) as u32;
+        let dy = dy.rem_euclid(self.height as i32) as u32;
+        if dx == 0 && dy == 0 {
+            return;
+        }
+
+        let board = self.read_region(BoundingBox {
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+        });
+        self.write_pattern(&board, dx, dy, PasteMode::Copy);
+    }
+}
@@ -0,0 +1,148 @@
+use wasm_bindgen::prelude::*;
+
+use crate::life_counter_combined::Universe;
+
+/// A pixel buffer for drawing any rectangle of a universe at any zoom,
+/// separate from the universe's own canvas.
+///
+/// Zoomed in, each pixel shows the cell under its centre, so cells may be
+/// any number of pixels across, whole or not. Zoomed out, each pixel
+/// averages the colours of every cell it covers, shading it by how many are
+/// alive.
+///
+/// A universe built with a cell size of zero keeps no canvas of its own, so
+/// a world too large to draw whole needs only the memory for a viewport.
+#[wasm_bindgen]
+pub struct Viewport {
+    width: u32,
+    height: u32,
+    pixels: Vec<u32>,
+}
+
+#[wasm_bindgen]
+impl Viewport {
+    /// A `width` by `height` pixel viewport.
+    pub fn new(width: u32, height: u32) -> Viewport {
+        Viewport {
+            width,
+            height,
+            pixels: vec![0xFF000000; width as usize * height as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Changes the size of the pixel buffer, clearing it.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        *self = Viewport::new(width, height);
+    }
+
+    pub fn pixels(&self) -> *const u32 {
+        self.pixels.as_ptr()
+    }
+
+    /// Draws the part of `universe` with its top-left corner at cell
+    /// `(x, y)` and `width` by `height` cells in size, stretched over the
+    /// whole viewport. The rectangle may run off any edge, wrapping around.
+    pub fn render(
+        &mut self,
+        universe: &Universe,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    ) -> Result<(), JsValue> {
+        let valid = |n: f64| n.is_finite() && n > 0.0;
+        if !x.is_finite() || !y.is_finite() || !valid(width) || !valid(height) {
+            return Err(JsValue::from_str(&format!(
+                "cannot view {}x{} cells from ({}, {})",
+                width, height, x, y
+            )));
+        }
+
+        let columns = spans(x, width, self.width, universe.width);
+        let rows = spans(y, height, self.height, universe.height);
+        for (py, row) in rows.iter().enumerate() {
+            for (px, column) in columns.iter().enumerate() {
+                let mut sums = [0u64; 4];
+                for &cy in row {
+                    for &cx in column {
+                        let colour = universe.cell_colour(universe.index(cx, cy));
+                        for (byte, sum) in sums.iter_mut().enumerate() {
+                            *sum += (colour >> (byte * 8) & 0xFF) as u64;
+                        }
+                    }
+                }
+                let count = (row.len() * column.len()) as u64;
+                self.pixels[py * self.width as usize + px] =
+                    sums.iter().enumerate().fold(0, |colour, (byte, &sum)| {
+                        colour | (((sum + count / 2) / count) as u32) << (byte * 8)
+                    });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The cells each of `pixels` pixels covers along one axis, for a view of
+/// `length` cells from `start` on a ring of `cells`.
+fn spans(start: f64, length: f64, pixels: u32, cells: u32) -> Vec<Vec<u32>> {
+    let scale = length / pixels as f64;
+    let wrap = |cell: i64| cell.rem_euclid(cells as i64) as u32;
+    (0..pixels)
+        .map(|pixel| {
+            // Wrapped onto the ring first, so far-off views keep the
+            // precision to tell neighbouring cells apart.
+            let from = (start + pixel as f64 * scale).rem_euclid(cells as f64);
+            if scale <= 1.0 {
+                vec![wrap((from + scale / 2.0).floor() as i64)]
+            } else {
+                // At least one cell, and at most a whole ring's worth.
+                let first = from.floor() as i64;
+                let last = ((from + scale).ceil() as i64).clamp(first + 1, first + cells as i64);
+                (first..last).map(wrap).collect()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::life_counter_combined::Cell;
+
+    #[test]
+    fn far_off_views() {
+        let mut universe = Universe::empty(16, 16, 0);
+        universe.recalculate();
+        for i in 0..16 {
+            universe.set(i, i, Cell::Alive);
+        }
+
+        // Zoomed out and far enough that neighbouring cells' offsets round
+        // to the same float.
+        for &(x, width) in &[(1e17, 15.0), (-1e17, 15.0), (1e300, 15.0), (0.0, 1e300)] {
+            for span in spans(x, width, 10, 16) {
+                assert!(!span.is_empty() && span.len() <= 16, "{} {}", x, width);
+                assert!(span.iter().all(|&cell| cell < 16));
+            }
+            Viewport::new(10, 10)
+                .render(&universe, x, x, width, width)
+                .unwrap();
+        }
+
+        // A whole number of rings away looks the same as the origin.
+        let mut near = Viewport::new(10, 10);
+        let mut far = Viewport::new(10, 10);
+        near.render(&universe, 0.0, 0.0, 15.0, 15.0).unwrap();
+        far.render(&universe, 16.0 * (1u64 << 40) as f64, 0.0, 15.0, 15.0)
+            .unwrap();
+        assert_eq!(near.pixels, far.pixels);
+    }
+}
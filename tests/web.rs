@@ -11,3 +11,17 @@ wasm_bindgen_test_configure!(run_in_browser);
 fn pass() {
     assert_eq!(1 + 1, 2);
 }
+
+#[wasm_bindgen_test]
+fn snapshot_round_trip() {
+    use wasm_game_of_life::Universe;
+
+    let mut universe = Universe::new(64, 64, 1);
+    universe.tick();
+    let snapshot = universe.snapshot();
+
+    let mut restored = Universe::new(32, 32, 1);
+    restored.restore(&snapshot).unwrap();
+    assert_eq!(restored.tick_count(), 1);
+    assert_eq!(restored.snapshot(), snapshot);
+}
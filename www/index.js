@@ -33,7 +33,10 @@ function drawUniverseCanvas() {
     WIDTH * HEIGHT * CELL_SIZE * CELL_SIZE * 4);
 
   const imageData = new ImageData(data, WIDTH * CELL_SIZE);
-  ctx.putImageData(imageData, 0, 0);
+  // Only upload the parts of the canvas which changed.
+  const rects = universe.take_dirty_rects();
+  for (let i = 0; i < rects.length; i += 4)
+    ctx.putImageData(imageData, 0, 0, rects[i], rects[i + 1], rects[i + 2], rects[i + 3]);
 }
 
 async function renderLoop() {